@@ -0,0 +1,195 @@
+use std::{sync::Mutex, time::Duration};
+
+use futures::future::Either;
+use tokio::sync::watch;
+use xilem::{
+    core::{adapt, fork, MessageProxy},
+    view::{async_repeat_raw, flex, label, Axis},
+    WidgetView,
+};
+
+use ut3_core::{
+    nested::Geometry,
+    search::{is_decisive, score_moves, win_probability, Limits, WIN},
+    BitPosition, GameOutcome, Player, Position,
+};
+
+use crate::{
+    eval_bar::eval_bar,
+    replay::{controls, Replay},
+    tile::{board, tile, Highlight, PlayTile},
+};
+
+/// How long each position is searched for before its scores are shown.
+const LIMITS: Limits = Limits {
+    depth: None,
+    nodes: None,
+    time: Some(Duration::from_secs(1)),
+};
+
+/// Stepping through a game with the engine's opinion of every position and move.
+pub struct Analysis {
+    replay: Replay,
+    /// The score of every legal move, as of the move count alongside.
+    scores: Option<(usize, Vec<(usize, i32)>)>,
+    /// The position to search, tagged with how many moves into the game it is.
+    send: watch::Sender<(usize, BitPosition)>,
+    for_task: Option<watch::Receiver<(usize, BitPosition)>>,
+}
+
+// the tiles are never playable
+impl PlayTile for Analysis {
+    fn play(&mut self, _: usize) {}
+}
+
+impl Analysis {
+    /// Starts at the end of the game that led to `position`.
+    pub fn new(position: Position) -> Self {
+        let replay = Replay::at_end(position);
+        let (send, for_task) = watch::channel((replay.ply(), BitPosition::from(replay.position())));
+        Analysis {
+            replay,
+            scores: None,
+            send,
+            for_task: Some(for_task),
+        }
+    }
+
+    fn position(&self) -> &Position {
+        self.replay.position()
+    }
+
+    fn ply(&self) -> usize {
+        self.replay.ply()
+    }
+
+    /// Has the search task look at the position being shown, if it isn't already, dropping
+    /// whatever it was looking at before.
+    fn search_shown(&mut self) {
+        let shown = (self.ply(), BitPosition::from(self.position()));
+        self.send.send_if_modified(|searching| {
+            let changed = *searching != shown;
+            *searching = shown;
+            changed
+        });
+    }
+
+    /// The scores of the moves in the position being shown, once they've been worked out.
+    fn scores(&self) -> Option<&[(usize, i32)]> {
+        match &self.scores {
+            Some((ply, scores)) if *ply == self.ply() => Some(scores),
+            _ => None,
+        }
+    }
+
+    /// Cross's chance of winning, from 0 to 1.
+    fn cross_share(&self) -> Option<f32> {
+        let best = match self.position().outcome() {
+            GameOutcome::Win(Player::Cross, _) => return Some(1.),
+            GameOutcome::Win(Player::Nought, _) => return Some(0.),
+            GameOutcome::Draw(_) => return Some(0.5),
+            GameOutcome::Ongoing => self.best_score()?,
+        };
+        let share = win_probability(best);
+        Some(match self.position().side_to_move() {
+            Player::Cross => share,
+            Player::Nought => 1. - share,
+        })
+    }
+
+    /// The score of the position for the player to move.
+    fn best_score(&self) -> Option<i32> {
+        self.scores()?.iter().map(|&(_, score)| score).max()
+    }
+
+    fn evaluation(&self) -> String {
+        match self.position().outcome() {
+            GameOutcome::Win(winner, _) => return format!("{winner} won"),
+            GameOutcome::Draw(_) => return "Drawn".to_owned(),
+            GameOutcome::Ongoing => {}
+        }
+        let (Some(best), Some(cross)) = (self.best_score(), self.cross_share()) else {
+            return "Thinking...".to_owned();
+        };
+        let us = self.position().side_to_move();
+        if is_decisive(best) {
+            let (winner, moves) = if best > 0 {
+                (us, WIN - best)
+            } else {
+                (!us, WIN + best)
+            };
+            let plural = if moves == 1 { "" } else { "s" };
+            format!("{winner} wins in {moves} move{plural}")
+        } else {
+            let cross = (cross * 100.).round();
+            format!("Cross {cross}%, Nought {}%", 100. - cross)
+        }
+    }
+
+    fn tile_highlight(&self, coord: usize) -> Highlight {
+        let score = self
+            .scores()
+            .and_then(|scores| scores.iter().find(|&&(c, _)| c == coord));
+        match score {
+            Some(&(_, score)) => Highlight::Heat(win_probability(score)),
+            None => Highlight::None,
+        }
+    }
+}
+
+/// Scores every move in each position sent, on its own thread, and sends the scores back unless
+/// the position has changed again by then.
+async fn analyse(
+    mut positions: watch::Receiver<(usize, BitPosition)>,
+    proxy: MessageProxy<(usize, Vec<(usize, i32)>)>,
+) {
+    loop {
+        let (ply, position) = *positions.borrow_and_update();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(score_moves(&position, LIMITS));
+        });
+        let Ok(scores) = rx.await else {
+            break;
+        };
+        let stale = positions.has_changed().unwrap_or(true);
+        if !stale && proxy.message((ply, scores)).is_err() {
+            break;
+        }
+        if positions.changed().await.is_err() {
+            break;
+        }
+    }
+}
+
+pub fn analysis(a: &mut Analysis) -> impl WidgetView<Analysis> {
+    let board = board(Geometry::STANDARD, |coord| {
+        tile(coord, a.position().tile(coord), false).highlight(a.tile_highlight(coord))
+    });
+    let steps = adapt(controls(&mut a.replay), |a: &mut Analysis, thunk| {
+        let result = thunk.call(&mut a.replay);
+        a.search_shown();
+        result
+    });
+    let ui = flex((
+        label(a.replay.status()),
+        flex((eval_bar(a.cross_share()), board)).direction(Axis::Horizontal),
+        label(a.evaluation()),
+        steps,
+    ))
+    .main_axis_alignment(xilem::view::MainAxisAlignment::Center);
+    let for_task = Mutex::new(a.for_task.take());
+    fork(
+        ui,
+        async_repeat_raw(
+            move |proxy| {
+                if let Some(positions) = for_task.lock().unwrap().take() {
+                    Either::Left(analyse(positions, proxy))
+                } else {
+                    Either::Right(std::future::ready(()))
+                }
+            },
+            |a: &mut Analysis, (ply, scores)| a.scores = Some((ply, scores)),
+        ),
+    )
+}
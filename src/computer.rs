@@ -0,0 +1,300 @@
+use std::{
+    fmt, io,
+    process::Stdio,
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    process::{ChildStdin, ChildStdout},
+    sync::mpsc::Receiver,
+};
+use ut3_core::{
+    engine::{Command, Response},
+    mcts::{best_move, Budget},
+    search::{search, Limits},
+    BitPosition, Player, Position,
+};
+use xilem::core::MessageProxy;
+
+/// The difficulty levels in the menu, easiest first.
+pub const DIFFICULTIES: [(&str, Budget); 4] = [
+    ("Easy", Budget::Playouts(100)),
+    ("Medium", Budget::Playouts(2000)),
+    ("Hard", Budget::Time(Duration::from_secs(1))),
+    ("Very hard", Budget::Time(Duration::from_secs(5))),
+];
+
+/// How long an external engine gets for each move.
+const ENGINE_MOVE_TIME: Duration = Duration::from_secs(1);
+
+/// How much longer than it was asked to take we wait for an engine before giving up on it, to
+/// allow for it being slow to start or to notice the time.
+const ENGINE_GRACE: Duration = Duration::from_secs(5);
+
+/// How long the search behind the hint button gets.
+const HINT_TIME: Duration = Duration::from_millis(500);
+
+/// A computer opponent in an [`Ultimate`](crate::Ultimate) game.
+pub struct Computer {
+    pub player: Player,
+    /// Kept so the game can be saved and picked up again against the same opponent.
+    pub opponent: Opponent,
+    /// Positions for the computer to find a move in.
+    pub send: tokio::sync::mpsc::Sender<Position>,
+}
+
+/// Who does the thinking for a [`Computer`]. Written like `playouts 2000`, `time 1000` (in
+/// milliseconds) or `engine python3 bot.py` in saved games.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Opponent {
+    /// The built-in tree search.
+    Builtin(Budget),
+    /// A program speaking the [engine protocol](ut3_core::engine), run with this command line.
+    Engine(String),
+}
+
+impl Opponent {
+    /// What to call the computer in a saved game, like `Computer (Medium)`, or the engine's
+    /// command line.
+    pub fn name(&self) -> String {
+        match self {
+            Opponent::Builtin(budget) => {
+                match DIFFICULTIES
+                    .iter()
+                    .find(|(_, difficulty)| difficulty == budget)
+                {
+                    Some((name, _)) => format!("Computer ({name})"),
+                    None => format!("Computer ({self})"),
+                }
+            }
+            Opponent::Engine(command) => command.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Opponent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Opponent::Builtin(Budget::Playouts(playouts)) => write!(f, "playouts {playouts}"),
+            Opponent::Builtin(Budget::Time(time)) => write!(f, "time {}", time.as_millis()),
+            Opponent::Engine(command) => write!(f, "engine {command}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownOpponent(pub String);
+
+impl fmt::Display for UnknownOpponent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown opponent {:?}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownOpponent {}
+
+impl FromStr for Opponent {
+    type Err = UnknownOpponent;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bad = || UnknownOpponent(s.to_owned());
+        let (kind, value) = s.split_once(' ').ok_or_else(bad)?;
+        let value = value.trim();
+        match kind {
+            "playouts" => Ok(Opponent::Builtin(Budget::Playouts(
+                value.parse().map_err(|_| bad())?,
+            ))),
+            "time" => Ok(Opponent::Builtin(Budget::Time(Duration::from_millis(
+                value.parse().map_err(|_| bad())?,
+            )))),
+            "engine" if !value.is_empty() => Ok(Opponent::Engine(value.to_owned())),
+            _ => Err(bad()),
+        }
+    }
+}
+
+/// Finds a move for each position sent, without holding up the UI, and sends back the moves. If
+/// the computer can't carry on, it sends back why and stops.
+pub async fn think(
+    positions: Receiver<Position>,
+    opponent: Opponent,
+    proxy: MessageProxy<Result<usize, String>>,
+) {
+    match opponent {
+        Opponent::Builtin(budget) => think_builtin(positions, budget, proxy).await,
+        Opponent::Engine(command) => {
+            if let Err(e) = think_engine(positions, &command, &proxy).await {
+                let _ = proxy.message(Err(e.to_string()));
+            }
+        }
+    }
+}
+
+/// Runs the search on its own thread for each position.
+async fn think_builtin(
+    mut positions: Receiver<Position>,
+    budget: Budget,
+    proxy: MessageProxy<Result<usize, String>>,
+) {
+    while let Some(position) = positions.recv().await {
+        let position = BitPosition::from(&position);
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(best_move(&position, budget, seed()));
+        });
+        let coord = match rx.await {
+            Ok(Some(coord)) => Ok(coord),
+            Ok(None) => Err("it didn't find a move".to_owned()),
+            // the thread panicked
+            Err(_) => Err("it crashed".to_owned()),
+        };
+        let failed = coord.is_err();
+        if proxy.message(coord).is_err() || failed {
+            break;
+        }
+    }
+}
+
+/// Finds the move the engine would play in each position sent, on its own thread, and sends it
+/// back with the position it's for.
+pub async fn find_hints(
+    mut positions: Receiver<BitPosition>,
+    proxy: MessageProxy<(BitPosition, usize)>,
+) {
+    while let Some(position) = positions.recv().await {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(search(&position, Limits::time(HINT_TIME)).best_move);
+        });
+        let Ok(Some(coord)) = rx.await else {
+            continue;
+        };
+        if proxy.message((position, coord)).is_err() {
+            break;
+        }
+    }
+}
+
+/// One end of a conversation with an engine process.
+struct EngineProcess {
+    stdin: ChildStdin,
+    stdout: tokio::io::Lines<BufReader<ChildStdout>>,
+}
+
+impl EngineProcess {
+    async fn send(&mut self, command: Command) -> io::Result<()> {
+        tracing::debug!(%command, "to engine");
+        self.stdin
+            .write_all(format!("{command}\n").as_bytes())
+            .await?;
+        self.stdin.flush().await
+    }
+
+    /// Reads lines until one that `wanted` picks something out of, skipping anything else. Gives
+    /// up if that takes longer than `limit`.
+    async fn wait_for<T>(
+        &mut self,
+        limit: Duration,
+        mut wanted: impl FnMut(Response) -> Option<T>,
+    ) -> io::Result<T> {
+        let read = async {
+            loop {
+                let Some(line) = self.stdout.next_line().await? else {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "the engine quit",
+                    ));
+                };
+                tracing::debug!(line, "from engine");
+                match line.parse() {
+                    Ok(response) => {
+                        if let Some(found) = wanted(response) {
+                            return Ok(found);
+                        }
+                    }
+                    // we'd be waiting forever for a move we've already been sent
+                    Err(e) if line.split_whitespace().next() == Some("bestmove") => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("the engine sent a move we don't understand: {e}"),
+                        ));
+                    }
+                    Err(e) => {
+                        tracing::warn!(line, %e, "engine sent something we don't understand")
+                    }
+                }
+            }
+        };
+        tokio::time::timeout(limit, read).await.unwrap_or_else(|_| {
+            Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!(
+                    "the engine didn't answer within {} seconds",
+                    limit.as_secs()
+                ),
+            ))
+        })
+    }
+}
+
+/// Starts the engine and asks it for a move in each position.
+async fn think_engine(
+    mut positions: Receiver<Position>,
+    command_line: &str,
+    proxy: &MessageProxy<Result<usize, String>>,
+) -> io::Result<()> {
+    let mut words = command_line.split_whitespace();
+    let program = words.next().ok_or(io::ErrorKind::InvalidInput)?;
+    let mut child = tokio::process::Command::new(program)
+        .args(words)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("couldn't run {program}: {e}")))?;
+    let mut engine = EngineProcess {
+        stdin: child.stdin.take().expect("stdin was piped"),
+        stdout: BufReader::new(child.stdout.take().expect("stdout was piped")).lines(),
+    };
+
+    engine.send(Command::Ut3).await?;
+    engine
+        .wait_for(ENGINE_GRACE, |response| {
+            (response == Response::Ut3Ok).then_some(())
+        })
+        .await?;
+    engine.send(Command::NewGame).await?;
+    while let Some(position) = positions.recv().await {
+        engine.send(Command::position(&position)).await?;
+        engine
+            .send(Command::Go(Limits::time(ENGINE_MOVE_TIME)))
+            .await?;
+        let best_move = engine
+            .wait_for(ENGINE_MOVE_TIME + ENGINE_GRACE, |response| match response {
+                Response::BestMove(best_move) => Some(best_move),
+                _ => None,
+            })
+            .await?;
+        // we only ask while the game is going
+        let Some(coord) = best_move else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the engine didn't pick a move",
+            ));
+        };
+        if proxy.message(Ok(coord)).is_err() {
+            break;
+        }
+    }
+    let _ = engine.send(Command::Quit).await;
+    Ok(())
+}
+
+/// Different every game, so the computer doesn't always play the same moves.
+fn seed() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |since| since.as_nanos() as u64)
+}
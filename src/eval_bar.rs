@@ -0,0 +1,126 @@
+use accesskit::Role;
+use masonry::{
+    kurbo::RoundedRect,
+    vello::{peniko::Fill, Scene},
+    AccessCtx, AccessEvent, Affine, BoxConstraints, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, PointerEvent, Size, StatusChange, TextEvent, Widget, WidgetId,
+};
+use smallvec::SmallVec;
+use xilem::{
+    core::{Message, MessageResult, Mut, View, ViewId},
+    Color, Pod, ViewCtx,
+};
+
+/// A vertical bar split between the players, with cross's share at the bottom. `cross` is from 0
+/// to 1, or `None` to show an empty bar while there's nothing to show.
+pub fn eval_bar(cross: Option<f32>) -> EvalBar {
+    EvalBar { cross }
+}
+
+pub struct EvalBar {
+    cross: Option<f32>,
+}
+
+impl<T, A> View<T, A, ViewCtx> for EvalBar {
+    type Element = Pod<EvalBarWidget>;
+    type ViewState = ();
+
+    fn build(&self, _: &mut ViewCtx) -> (Self::Element, Self::ViewState) {
+        (Pod::new(EvalBarWidget { cross: self.cross }), ())
+    }
+
+    fn rebuild<'el>(
+        &self,
+        prev: &Self,
+        _: &mut Self::ViewState,
+        _: &mut ViewCtx,
+        mut element: Mut<'el, Self::Element>,
+    ) -> Mut<'el, Self::Element> {
+        if self.cross != prev.cross {
+            element.widget.cross = self.cross;
+            element.ctx.request_paint();
+        }
+        element
+    }
+
+    fn teardown(&self, _: &mut Self::ViewState, _: &mut ViewCtx, _: Mut<'_, Self::Element>) {}
+
+    fn message(
+        &self,
+        _: &mut Self::ViewState,
+        _: &[ViewId],
+        message: Box<dyn Message>,
+        _: &mut T,
+    ) -> MessageResult<A, Box<dyn Message>> {
+        MessageResult::Stale(message)
+    }
+}
+
+const WIDTH: f64 = 16.;
+/// About the height of the board.
+const HEIGHT: f64 = 236.;
+
+pub struct EvalBarWidget {
+    cross: Option<f32>,
+}
+
+impl Widget for EvalBarWidget {
+    fn on_pointer_event(&mut self, _: &mut EventCtx, _: &PointerEvent) {}
+
+    fn on_text_event(&mut self, _: &mut EventCtx, _: &TextEvent) {}
+
+    fn on_access_event(&mut self, _: &mut EventCtx, _: &AccessEvent) {}
+
+    fn on_status_change(&mut self, _: &mut LifeCycleCtx, _: &StatusChange) {}
+
+    fn lifecycle(&mut self, _: &mut LifeCycleCtx, _: &LifeCycle) {}
+
+    fn layout(&mut self, _: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        bc.constrain((WIDTH, HEIGHT))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        let (w, h) = ctx.size().into();
+        let Some(cross) = self.cross else {
+            scene.fill(
+                Fill::NonZero,
+                Affine::IDENTITY,
+                Color::rgb8(40, 40, 40),
+                None,
+                &RoundedRect::new(0., 0., w, h, 2.),
+            );
+            return;
+        };
+        let split = h * (1. - cross.clamp(0., 1.) as f64);
+        scene.fill(
+            Fill::NonZero,
+            Affine::IDENTITY,
+            Color::TURQUOISE,
+            None,
+            &RoundedRect::new(0., 0., w, split, 2.),
+        );
+        scene.fill(
+            Fill::NonZero,
+            Affine::IDENTITY,
+            Color::ORANGE_RED,
+            None,
+            &RoundedRect::new(0., split, w, h, 2.),
+        );
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::Meter
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        if let Some(cross) = self.cross {
+            ctx.current_node().set_numeric_value(cross as f64);
+        }
+        ctx.current_node().set_min_numeric_value(0.);
+        ctx.current_node().set_max_numeric_value(1.);
+    }
+
+    fn children_ids(&self) -> SmallVec<[WidgetId; 16]> {
+        SmallVec::new_const()
+    }
+}
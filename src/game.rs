@@ -1,91 +1,261 @@
-use crate::Player;
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc::error::TrySendError;
+use ut3_core::{
+    notation::{Global, Minisquare},
+    BitPosition, GameOutcome, IllegalMove, MinisquareState, Player, Termination,
+};
+
+/// How long past zero we let a network opponent's clock run before flagging them, for their
+/// move to arrive.
+const NETWORK_GRACE: Duration = Duration::from_secs(3);
 
 // sorry
 impl crate::Ultimate {
+    /// Whether the local player can click on this tile right now. The rules themselves don't care
+    /// who is sitting at this computer, so that part is decided here.
     pub fn is_playable(&self, coord: usize) -> bool {
-        let mut is_playable =
-            self.tiles[coord].is_none() && self.whose_turn == Some(self.local_player);
-        if let Some(last_move) = self.history.last() {
-            let open_minisquare_x = last_move % 3;
-            let open_minisquare_y = (last_move / 9) % 3;
-            let current_minisquare_x = (coord % 9) / 3;
-            let current_minisquare_y = coord / 27;
-            is_playable &= (open_minisquare_x == current_minisquare_x
-                && open_minisquare_y == current_minisquare_y)
-                || self.minisquares[open_minisquare_y * 3 + open_minisquare_x].is_some();
-            is_playable &=
-                self.minisquares[current_minisquare_y * 3 + current_minisquare_x].is_none();
+        self.position.whose_turn() == Some(self.local_player)
+            && self.position.is_legal(coord)
+            && self.failure.is_none()
+    }
+
+    pub fn handle_move(&mut self, player: Player, coord: usize) -> Result<(), IllegalMove> {
+        let effects = self.position.try_play_as(player, coord)?;
+        self.hint = None;
+        if let Some(clock) = &mut self.clock {
+            let now = Instant::now();
+            clock.press(now);
+            if effects.outcome.is_over() {
+                clock.stop(now);
+            }
+        }
+
+        eprintln!("{player:?} played {}", Global(coord));
+        match effects.closed_minisquare {
+            Some((minisquare, MinisquareState::Won(_))) => {
+                eprintln!("won {}", Minisquare(minisquare))
+            }
+            Some((minisquare, _)) => eprintln!("drew {}", Minisquare(minisquare)),
+            None => {}
+        }
+        match effects.outcome {
+            GameOutcome::Ongoing => {}
+            GameOutcome::Win(winner, reason) => eprintln!("{winner:?} won the game ({reason:?})"),
+            GameOutcome::Draw(reason) => eprintln!("the game was drawn ({reason:?})"),
+        }
+        self.autosave();
+        Ok(())
+    }
+
+    fn autosave(&self) {
+        if let Err(e) = crate::save::autosave(self) {
+            tracing::warn!(%e, "couldn't autosave the game");
+        }
+    }
+
+    /// A line of text describing the state of the game, for showing above the board.
+    pub fn status(&self) -> String {
+        let is_local_multiplayer = self.is_local_multiplayer();
+        if let (Some(failure), GameOutcome::Ongoing) = (&self.failure, self.position.outcome()) {
+            return failure.clone();
+        }
+        match self.position.outcome() {
+            GameOutcome::Ongoing if is_local_multiplayer => {
+                format!("{} to move", self.local_player)
+            }
+            GameOutcome::Ongoing if self.position.whose_turn() == Some(self.local_player) => {
+                "Your move".to_owned()
+            }
+            GameOutcome::Ongoing if self.computer.is_some() => "Thinking...".to_owned(),
+            GameOutcome::Ongoing => "Waiting for opponent's move...".to_owned(),
+            GameOutcome::Win(winner, reason) => {
+                let how = match reason {
+                    Termination::OuterLine => "three in a row",
+                    Termination::NoMovesLeft => "no moves left",
+                    Termination::MinisquareCount => "winning more minisquares",
+                    Termination::Timeout => "timeout",
+                };
+                if is_local_multiplayer {
+                    format!("{winner} wins by {how}")
+                } else if winner == self.local_player {
+                    format!("You win by {how}")
+                } else {
+                    format!("You lose by {how}")
+                }
+            }
+            GameOutcome::Draw(_) => "Draw - no moves left".to_owned(),
+        }
+    }
+
+    pub fn rules_description(&self) -> String {
+        let rules = self.position.rules();
+        let variations: Vec<_> = [
+            (rules.play_in_won_minisquares, "won minisquares stay open"),
+            (
+                rules.free_move_anywhere,
+                "free moves can go in won minisquares",
+            ),
+            (rules.minisquare_tiebreak, "draws broken by minisquares won"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, description)| enabled.then_some(description))
+        .collect();
+        if variations.is_empty() {
+            "Standard rules".to_owned()
+        } else {
+            format!("Rules: {}", variations.join(", "))
+        }
+    }
+
+    /// Whether this is a hot-seat game, where both players share this computer.
+    pub fn is_local_multiplayer(&self) -> bool {
+        self.send.is_none() && self.computer.is_none()
+    }
+
+    /// Sets the computer thinking if it's their turn. The move comes back through `handle_move`,
+    /// like an opponent's over the network.
+    pub fn ask_computer(&mut self) {
+        let Some(computer) = &self.computer else {
+            return;
         };
-        is_playable
-    }
-
-    pub fn handle_move(&mut self, player: Player, coord: usize) {
-        assert_eq!(self.whose_turn, Some(player));
-        self.tiles[coord] = Some(player);
-
-        // did we win a minisquare?
-        let inner_x = coord % 3;
-        let inner_y = (coord / 9) % 3;
-        let outer_x = (coord % 9) / 3;
-        let outer_y = coord / 27;
-        let us = player;
-        let topleft = outer_y * 27 + outer_x * 3;
-        let won_axis = (self.tiles[topleft + inner_y * 9] == Some(us)
-            && self.tiles[topleft + inner_y * 9 + 1] == Some(us)
-            && self.tiles[topleft + inner_y * 9 + 2] == Some(us))
-            || (self.tiles[topleft + inner_x] == Some(us)
-                && self.tiles[topleft + 1 * 9 + inner_x] == Some(us)
-                && self.tiles[topleft + 2 * 9 + inner_x] == Some(us));
-        let won_diagonal = ([0, 2].contains(&inner_x) && [0, 2].contains(&inner_y))
-            && self.tiles[topleft + inner_y * 9 + inner_x] == Some(us)
-            && self.tiles[topleft + 9 + 1] == Some(us)
-            && self.tiles[topleft + (inner_y ^ 2) * 9 + (inner_x ^ 2)] == Some(us) /* trust */;
-        if won_axis || won_diagonal {
-            self.minisquares[outer_y * 3 + outer_x] = Some(us);
-            eprintln!("won {}, {}", outer_x, outer_y);
-        }
-
-        // did we win the game?
-        let mut won_axis = self.minisquares.chunks(3).any(|row| row == [Some(us); 3]);
-        for i in 0..3 {
-            won_axis |= self.minisquares[i] == Some(us)
-                && self.minisquares[i + 3] == Some(us)
-                && self.minisquares[i + 6] == Some(us);
-        }
-        let won_diagonal = self.minisquares[4] == Some(us)
-            && (self.minisquares[0] == Some(us) && self.minisquares[8] == Some(us)
-                || self.minisquares[2] == Some(us) && self.minisquares[6] == Some(us));
-
-        self.history.push(coord);
-
-        if won_axis || won_diagonal {
-            self.whose_turn = None;
-            eprintln!("won the game");
+        if self.position.whose_turn() != Some(computer.player) || self.failure.is_some() {
             return;
         }
-        let whose_turn = match self.whose_turn {
-            Some(Player::Nought) => Player::Cross,
-            Some(Player::Cross) => Player::Nought,
-            None => unreachable!("there's an assert further up"),
+        match computer.send.try_send(self.position.clone()) {
+            Ok(()) => {}
+            // it's already thinking about this position, e.g. if it was asked twice
+            Err(TrySendError::Full(_)) => {}
+            // the task sends back why it stopped, which replaces this
+            Err(TrySendError::Closed(_)) => self.computer_failed("it stopped".to_owned()),
+        }
+    }
+
+    /// Stops the game because the computer can't carry on, e.g. if the engine crashed.
+    pub fn computer_failed(&mut self, error: String) {
+        tracing::error!(error, "the computer stopped working");
+        self.fail(format!("The computer stopped working: {error}"));
+    }
+
+    /// Stops the game because the opponent sent something we can't play on from, or went away.
+    pub fn connection_failed(&mut self, error: String) {
+        tracing::error!(error, "lost the connection to the opponent");
+        self.fail(format!("Lost the connection to the opponent: {error}"));
+    }
+
+    fn fail(&mut self, message: String) {
+        self.failure = Some(message);
+        if let Some(clock) = &mut self.clock {
+            clock.stop(Instant::now());
+        }
+    }
+
+    /// Takes back the last move. Only possible in local games, since the opponent would have to
+    /// agree otherwise.
+    pub fn undo(&mut self) {
+        if self.is_local_multiplayer() && self.position.undo().is_some() {
+            self.hint = None;
+            self.pass_to_side_to_move();
+            self.autosave();
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if self.is_local_multiplayer() && self.position.redo().is_some() {
+            self.hint = None;
+            self.pass_to_side_to_move();
+            self.autosave();
+        }
+    }
+
+    /// Hints are for practice, so not in network games.
+    pub fn can_hint(&self) -> bool {
+        self.send.is_none()
+            && self.failure.is_none()
+            && self.position.whose_turn() == Some(self.local_player)
+    }
+
+    /// Asks for the move the engine would play for the local player, which is highlighted once
+    /// it's been found.
+    pub fn hint(&mut self) {
+        // if one is already being worked out, this click is dropped
+        if self.can_hint() {
+            let _ = self.hint_send.try_send(BitPosition::from(&self.position));
+        }
+    }
+
+    /// Highlights a hint from the hint task, unless the game has moved on since it was asked for.
+    pub fn show_hint(&mut self, position: BitPosition, coord: usize) {
+        if self.can_hint() && BitPosition::from(&self.position) == position {
+            self.hint = Some(coord);
+        }
+    }
+
+    pub fn pass_to_side_to_move(&mut self) {
+        if let Some(whose_turn) = self.position.whose_turn() {
+            // if the game hasn't ended
+            self.local_player = whose_turn;
+            if let Some(clock) = &mut self.clock {
+                if clock.running() != Some(whose_turn) {
+                    clock.start(whose_turn, Instant::now());
+                }
+            }
+        }
+    }
+
+    /// Ends the game if the player to move has run out of time. In network games our copy of the
+    /// opponent's clock runs ahead of theirs by however long their moves take to get here, so
+    /// they get [`NETWORK_GRACE`] on top before we flag them ourselves. Normally they'll have told
+    /// us first, but this way a client that's hung or gone quiet can't stall the game.
+    pub fn check_clock(&mut self) {
+        let Some(clock) = &self.clock else {
+            return;
         };
-        self.whose_turn = Some(whose_turn);
+        let now = Instant::now();
+        let Some(player) = clock.flagged(now) else {
+            return;
+        };
+        if let Some(tx) = &self.send {
+            if player != self.local_player {
+                if clock.flagged_by(NETWORK_GRACE, now).is_none() {
+                    return;
+                }
+            } else if let Err(e) = tx.try_send((crate::OUT_OF_TIME.into(), Duration::ZERO)) {
+                tracing::error!(%e, "couldn't tell the opponent we ran out of time");
+            }
+        }
+        self.time_out(player);
+    }
+
+    /// Ends the game with `player` losing on time.
+    pub fn time_out(&mut self, player: Player) {
+        if let Some(clock) = &mut self.clock {
+            clock.stop(Instant::now());
+        }
+        self.position.time_out(player);
+        self.hint = None;
+        eprintln!("{player:?} ran out of time");
+        self.autosave();
     }
 
     pub fn make_move(&mut self, coord: usize) {
-        assert!(self.is_playable(coord));
-        self.handle_move(self.local_player, coord);
+        if let Err(e) = self.handle_move(self.local_player, coord) {
+            tracing::error!(coord = %Global(coord), %e, "tried to make an illegal move");
+            return;
+        }
         match self.send {
             Some(ref tx) => {
-                tx.try_send(coord).expect(
+                let remaining = self
+                    .clock
+                    .as_ref()
+                    .map(|clock| clock.remaining(self.local_player, Instant::now()))
+                    .unwrap_or_default();
+                tx.try_send((coord, remaining)).expect(
                     "we should have flushed the previous move, else we can't make this move!",
                 );
             }
-            None => {
-                if let Some(whose_turn) = self.whose_turn {
-                    // if the game hasn't ended
-                    self.local_player = whose_turn; // local multiplayer
-                }
-            }
+            None if self.computer.is_some() => self.ask_computer(),
+            None => self.pass_to_side_to_move(), // local multiplayer
         }
     }
 }
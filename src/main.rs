@@ -1,4 +1,8 @@
-use std::sync::Mutex;
+use std::{
+    path::Path,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 
 use futures::future::Either;
 use tokio::{
@@ -8,22 +12,47 @@ use tokio::{
 };
 use xilem::{
     core::{adapt, fork, MessageProxy},
-    view::{async_repeat, async_repeat_raw, button, flex, label, sized_box, textbox, Axis},
+    view::{
+        async_repeat, async_repeat_raw, button, checkbox, flex, label, sized_box, textbox, Axis,
+    },
     WidgetView, Xilem,
 };
 
+mod analysis;
+mod computer;
 mod disable;
+mod eval_bar;
 mod game;
+mod nested_game;
+mod replay;
+mod save;
+mod shortcut;
+mod slider;
 mod tile;
 
+use analysis::{analysis, Analysis};
+use computer::{Computer, Opponent, DIFFICULTIES};
 use disable::disable_if;
-use tile::{tile, Tile};
+use nested_game::{nested_game, NestedGame};
+use replay::{replay, Replay};
+use shortcut::{on_shortcut, Shortcut};
+use slider::slider;
+use tile::{board, tile, Highlight, Tile};
+use ut3_core::{
+    clock::{BadTimeControl, Clock, TimeControl},
+    nested::{Geometry, NestedPosition},
+    notation::Global,
+    BitPosition, Player, Position, Ruleset,
+};
 
 enum AppState {
     MainMenu(MainMenu),
-    WaitingForOpponent,
+    WaitingForOpponent(Settings),
     Connecting(String),
     InGame(Ultimate),
+    Replay(Replay),
+    Analysis(Analysis),
+    NestedGame(NestedGame),
 }
 
 impl AppState {
@@ -40,85 +69,251 @@ impl AppState {
             _ => panic!("expected in-game but app was in another state!"),
         }
     }
+
+    fn expect_replay_mut(&mut self) -> &mut Replay {
+        match self {
+            AppState::Replay(replay) => replay,
+            _ => panic!("expected replay but app was in another state!"),
+        }
+    }
+
+    fn expect_analysis_mut(&mut self) -> &mut Analysis {
+        match self {
+            AppState::Analysis(analysis) => analysis,
+            _ => panic!("expected analysis but app was in another state!"),
+        }
+    }
+
+    fn expect_nested_game_mut(&mut self) -> &mut NestedGame {
+        match self {
+            AppState::NestedGame(game) => game,
+            _ => panic!("expected nested game but app was in another state!"),
+        }
+    }
+}
+
+/// What the host of a game decides on.
+#[derive(Clone, Copy)]
+struct Settings {
+    ruleset: Ruleset,
+    time_control: Option<TimeControl>,
 }
 
 struct MainMenu {
     remote_address: String,
+    ruleset: Ruleset,
+    /// As typed, like `300+5`. Empty for no clock.
+    time_control: String,
+    geometry: Geometry,
+    /// The side to take against the computer.
+    play_as: Player,
+    /// An index into `DIFFICULTIES`.
+    difficulty: usize,
+    /// How to run an external engine, like `ut3-engine` or `python3 bot.py`.
+    engine_command: String,
+    /// Why the last attempt to connect to a game failed.
+    connect_error: String,
+    load_path: String,
+    load_error: String,
+    /// Whether there's an unfinished autosave to resume, checked once at startup.
+    can_resume: bool,
+}
+
+impl MainMenu {
+    fn new() -> Self {
+        MainMenu {
+            remote_address: String::new(),
+            ruleset: Ruleset::STANDARD,
+            time_control: String::new(),
+            geometry: Geometry::STANDARD,
+            play_as: Player::Cross,
+            difficulty: 1,
+            engine_command: String::new(),
+            connect_error: String::new(),
+            load_path: save::DEFAULT_PATH.to_owned(),
+            load_error: String::new(),
+            can_resume: save::load_unfinished_autosave().is_ok_and(|ult| ult.is_some()),
+        }
+    }
+
+    fn settings(&self) -> Result<Settings, BadTimeControl> {
+        let time_control = match self.time_control.trim() {
+            "" => None,
+            time_control => Some(time_control.parse()?),
+        };
+        Ok(Settings {
+            ruleset: self.ruleset,
+            time_control,
+        })
+    }
 }
 
 struct Ultimate {
-    tiles: [Option<Player>; 81],
-    minisquares: [Option<Player>; 9],
-    whose_turn: Option<Player>, // None if ended
+    position: Position,
     local_player: Player,
-    history: Vec<usize>, // coord
 
-    send: Option<Sender<usize>>,
+    clock: Option<Clock>,
+
+    /// Our moves, with how long we had left after making them.
+    send: Option<Sender<(usize, Duration)>>,
     recv: Option<tokio::net::tcp::OwnedReadHalf>,
 
-    for_recv_task: Option<(Receiver<usize>, tokio::net::tcp::OwnedWriteHalf)>,
+    for_recv_task: Option<(Receiver<(usize, Duration)>, tokio::net::tcp::OwnedWriteHalf)>,
+
+    computer: Option<Computer>,
+    for_computer_task: Option<(Receiver<Position>, Opponent)>,
+    /// Why the game can't carry on, like the computer crashing or the opponent disconnecting,
+    /// shown in place of the status.
+    failure: Option<String>,
+
+    /// The move the engine suggested, until a move is made.
+    hint: Option<usize>,
+    /// Positions to find hints in.
+    hint_send: Sender<BitPosition>,
+    for_hint_task: Option<Receiver<BitPosition>>,
+
+    save_path: String,
+    /// The result of the last save or load, shown next to the buttons.
+    file_message: String,
 }
 
 impl Ultimate {
-    fn local_multiplayer() -> Self {
+    fn local_multiplayer(settings: Settings) -> Self {
+        let (hint_send, for_hint_task) = tokio::sync::mpsc::channel(1);
         Ultimate {
-            tiles: [None; 81],
-            minisquares: [None; 9],
-            whose_turn: Some(Player::Cross),
+            position: Position::with_rules(settings.ruleset),
             local_player: Player::Cross,
-            history: Vec::new(),
+
+            clock: start_clock(settings.time_control),
 
             send: None,
             recv: None,
 
             for_recv_task: None,
+
+            computer: None,
+            for_computer_task: None,
+            failure: None,
+
+            hint: None,
+            hint_send,
+            for_hint_task: Some(for_hint_task),
+
+            save_path: save::DEFAULT_PATH.to_owned(),
+            file_message: String::new(),
         }
     }
 
-    fn network_multiplayer(stream: TcpStream, local_player: Player) -> Self {
+    /// A game carrying on from `position`, against `computer` if it's the computer's side and
+    /// who plays it, or hot-seat otherwise. Hot-seat games keep their history to undo.
+    fn resume(
+        position: Position,
+        time_control: Option<TimeControl>,
+        computer: Option<(Player, Opponent)>,
+    ) -> Self {
+        let settings = Settings {
+            ruleset: position.rules(),
+            time_control,
+        };
+        let mut ult = Ultimate::local_multiplayer(settings);
+        ult.position = position;
+        if let Some((player, opponent)) = computer {
+            let (ui_tx, task_rx) = tokio::sync::mpsc::channel(1);
+            ult.local_player = !player;
+            ult.computer = Some(Computer {
+                player,
+                send: ui_tx,
+                opponent: opponent.clone(),
+            });
+            ult.for_computer_task = Some((task_rx, opponent));
+        }
+        if let Some(clock) = &mut ult.clock {
+            match ult.position.whose_turn() {
+                Some(whose_turn) => clock.start(whose_turn, Instant::now()),
+                None => clock.stop(Instant::now()),
+            }
+        }
+        if ult.computer.is_some() {
+            ult.ask_computer();
+        } else {
+            ult.pass_to_side_to_move();
+        }
+        ult
+    }
+
+    fn network_multiplayer(stream: TcpStream, local_player: Player, settings: Settings) -> Self {
         let (net_rx, net_tx) = stream.into_split();
-        let (ui_tx, task_rx) = tokio::sync::mpsc::channel::<usize>(1);
+        let (ui_tx, task_rx) = tokio::sync::mpsc::channel(1);
+        let (hint_send, for_hint_task) = tokio::sync::mpsc::channel(1);
         Ultimate {
-            tiles: [None; 81],
-            minisquares: [None; 9],
-            whose_turn: Some(Player::Cross),
+            position: Position::with_rules(settings.ruleset),
             local_player,
-            history: Vec::new(),
+
+            clock: start_clock(settings.time_control),
 
             recv: Some(net_rx),
             send: Some(ui_tx),
 
             for_recv_task: Some((task_rx, net_tx)),
+
+            computer: None,
+            for_computer_task: None,
+            failure: None,
+
+            hint: None,
+            hint_send,
+            for_hint_task: Some(for_hint_task),
+
+            save_path: save::DEFAULT_PATH.to_owned(),
+            file_message: String::new(),
+        }
+    }
+
+    /// A game against the built-in search or an external engine.
+    fn vs_computer(settings: Settings, local_player: Player, opponent: Opponent) -> Self {
+        Ultimate::resume(
+            Position::with_rules(settings.ruleset),
+            settings.time_control,
+            Some((!local_player, opponent)),
+        )
+    }
+
+    /// How long `player` has left, for showing next to the board.
+    fn clock_text(&self, player: Player) -> String {
+        let Some(clock) = &self.clock else {
+            return String::new();
+        };
+        let remaining = clock.remaining(player, Instant::now());
+        let (minutes, seconds) = (remaining.as_secs() / 60, remaining.as_secs() % 60);
+        if remaining < Duration::from_secs(10) {
+            let tenths = remaining.subsec_millis() / 100;
+            format!("{player} {minutes}:{seconds:02}.{tenths}")
+        } else {
+            format!("{player} {minutes}:{seconds:02}")
         }
     }
 
     // just here to shrink the syntax in app() lol
     fn tile(&self, coord: usize) -> Tile {
-        tile(coord, self.tiles[coord], self.is_playable(coord))
+        let highlight = if self.hint == Some(coord) {
+            Highlight::Hint
+        } else {
+            Highlight::None
+        };
+        tile(coord, self.position.tile(coord), self.is_playable(coord)).highlight(highlight)
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum Player {
-    Nought,
-    Cross,
-}
-
-impl std::ops::Not for Player {
-    type Output = Self;
-    fn not(self) -> Self::Output {
-        match self {
-            Player::Nought => Player::Cross,
-            Player::Cross => Player::Nought,
-        }
-    }
+/// Cross moves first, so their clock starts straight away.
+fn start_clock(time_control: Option<TimeControl>) -> Option<Clock> {
+    let mut clock = Clock::new(time_control?);
+    clock.start(Player::Cross, Instant::now());
+    Some(clock)
 }
 
 fn main() {
     let ev_builder = xilem::EventLoop::with_user_event();
-    let app_state = AppState::MainMenu(MainMenu {
-        remote_address: String::new(),
-    });
+    let app_state = AppState::MainMenu(MainMenu::new());
     Xilem::new(app_state, app)
         .run_windowed(ev_builder, "Ultimate 3".to_owned())
         .unwrap();
@@ -127,12 +322,22 @@ fn main() {
 fn app(s: &mut AppState) -> impl WidgetView<AppState> {
     match s {
         AppState::MainMenu(menu_state) => menu(menu_state).boxed(),
-        AppState::WaitingForOpponent => fork(
-            label("Waiting for opponent..."),
-            async_repeat(listen_for_opponent, |s, stream| {
-                *s = AppState::InGame(Ultimate::network_multiplayer(stream, Player::Cross));
-            }),
-        )
+        AppState::WaitingForOpponent(settings) => {
+            let settings = *settings;
+            fork(
+                label("Waiting for opponent..."),
+                async_repeat(
+                    move |proxy| listen_for_opponent(proxy, settings),
+                    |s, (stream, settings)| {
+                        *s = AppState::InGame(Ultimate::network_multiplayer(
+                            stream,
+                            Player::Cross,
+                            settings,
+                        ));
+                    },
+                ),
+            )
+        }
         .boxed(),
         AppState::Connecting(address) => {
             let address = address.clone();
@@ -140,9 +345,22 @@ fn app(s: &mut AppState) -> impl WidgetView<AppState> {
                 label("Connecting to opponent..."),
                 async_repeat_raw(
                     move |proxy| connect_to_opponent(proxy, address.clone()),
-                    |s, stream| {
-                        *s =
-                            AppState::InGame(Ultimate::network_multiplayer(stream, Player::Nought));
+                    |s, connection| match connection {
+                        Ok((stream, settings)) => {
+                            *s = AppState::InGame(Ultimate::network_multiplayer(
+                                stream,
+                                Player::Nought,
+                                settings,
+                            ));
+                        }
+                        Err(e) => {
+                            let mut menu = MainMenu::new();
+                            if let AppState::Connecting(address) = s {
+                                menu.remote_address = std::mem::take(address);
+                            }
+                            menu.connect_error = format!("Couldn't connect: {e}");
+                            *s = AppState::MainMenu(menu);
+                        }
                     },
                 ),
             )
@@ -151,100 +369,531 @@ fn app(s: &mut AppState) -> impl WidgetView<AppState> {
         AppState::InGame(ult) => {
             // eeewwwwww
             let net_rx = Mutex::new(ult.recv.take());
-            let listen_for_move = move |proxy: MessageProxy<u8>| {
+            let listen_for_move = move |proxy: MessageProxy<Result<(u8, u32), String>>| {
                 if let Some(mut net_rx) = net_rx.lock().unwrap().take() {
                     Either::Left(async move {
-                        while let Ok(coord) = net_rx.read_u8().await {
-                            let _ = proxy.message(coord);
-                        }
+                        let error = loop {
+                            let coord = match net_rx.read_u8().await {
+                                Ok(coord) => coord,
+                                Err(e) => break e,
+                            };
+                            let remaining_ms = match net_rx.read_u32().await {
+                                Ok(remaining_ms) => remaining_ms,
+                                Err(e) => break e,
+                            };
+                            if proxy.message(Ok((coord, remaining_ms))).is_err() {
+                                return;
+                            }
+                        };
+                        // only shown if the game isn't over, when hanging up is expected
+                        let _ = proxy.message(Err(match error.kind() {
+                            std::io::ErrorKind::UnexpectedEof => "they disconnected".to_owned(),
+                            _ => error.to_string(),
+                        }));
                     })
                 } else {
                     Either::Right(std::future::ready(()))
                 }
             };
-            let on_receive_move = |s: &mut Ultimate, coord| {
-                s.handle_move(!s.local_player, coord as usize);
+            let on_receive_move = |s: &mut Ultimate, message: Result<(u8, u32), String>| {
+                // the boards may not match any more, so nothing else they send counts
+                if s.failure.is_some() {
+                    return;
+                }
+                let (coord, remaining_ms) = match message {
+                    Ok(message) => message,
+                    Err(e) => return s.connection_failed(e),
+                };
+                if coord == OUT_OF_TIME {
+                    s.time_out(!s.local_player);
+                    return;
+                }
+                if let Err(e) = s.handle_move(!s.local_player, coord as usize) {
+                    let coord = Global(coord.into());
+                    return s.connection_failed(format!("they sent an illegal move, {coord}: {e}"));
+                }
+                // their clock is the one that counts for them, since ours is behind by however
+                // long the move took to get here
+                let opponent = !s.local_player;
+                if let Some(clock) = &mut s.clock {
+                    let remaining = Duration::from_millis(remaining_ms.into());
+                    clock.set_remaining(opponent, remaining, Instant::now());
+                }
             };
-            adapt(
-                fork(
-                    game(ult),
-                    async_repeat_raw(listen_for_move, on_receive_move),
+            let for_computer_task = Mutex::new(ult.for_computer_task.take());
+            let think = move |proxy: MessageProxy<Result<usize, String>>| {
+                if let Some((positions, opponent)) = for_computer_task.lock().unwrap().take() {
+                    Either::Left(computer::think(positions, opponent, proxy))
+                } else {
+                    Either::Right(std::future::ready(()))
+                }
+            };
+            let on_computer_move = |s: &mut Ultimate, coord: Result<usize, String>| {
+                let Some(player) = s.computer.as_ref().map(|computer| computer.player) else {
+                    return;
+                };
+                let coord = match coord {
+                    Ok(coord) => coord,
+                    Err(e) => return s.computer_failed(e),
+                };
+                // the game might have ended on time while it was thinking
+                if s.position.whose_turn() != Some(player) {
+                    return;
+                }
+                if let Err(e) = s.handle_move(player, coord) {
+                    s.computer_failed(format!("it picked an illegal move, {}: {e}", Global(coord)));
+                }
+            };
+            let for_hint_task = Mutex::new(ult.for_hint_task.take());
+            let find_hints = move |proxy: MessageProxy<(BitPosition, usize)>| {
+                if let Some(positions) = for_hint_task.lock().unwrap().take() {
+                    Either::Left(computer::find_hints(positions, proxy))
+                } else {
+                    Either::Right(std::future::ready(()))
+                }
+            };
+            let on_hint = |s: &mut Ultimate, (position, coord): (BitPosition, usize)| {
+                s.show_hint(position, coord);
+            };
+            let is_over = ult.position.outcome().is_over();
+            flex((
+                adapt(
+                    fork(
+                        fork(
+                            fork(
+                                game(ult),
+                                async_repeat_raw(listen_for_move, on_receive_move),
+                            ),
+                            async_repeat_raw(think, on_computer_move),
+                        ),
+                        async_repeat_raw(find_hints, on_hint),
+                    ),
+                    |s: &mut AppState, thunk| thunk.call(s.expect_game_mut()),
                 ),
-                |s: &mut AppState, thunk| thunk.call(s.expect_game_mut()),
-            )
+                flex((
+                    disable_if(
+                        !is_over,
+                        button("Watch replay", |s: &mut AppState| {
+                            let position = s.expect_game_mut().position.clone();
+                            *s = AppState::Replay(Replay::new(position));
+                        }),
+                    ),
+                    disable_if(
+                        !is_over,
+                        button("Analyse", |s: &mut AppState| {
+                            let position = s.expect_game_mut().position.clone();
+                            *s = AppState::Analysis(Analysis::new(position));
+                        }),
+                    ),
+                ))
+                .direction(Axis::Horizontal),
+            ))
             .boxed()
         }
+        AppState::NestedGame(g) => flex((
+            adapt(nested_game(g), |s: &mut AppState, thunk| {
+                thunk.call(s.expect_nested_game_mut())
+            }),
+            button("Main menu", |s: &mut AppState| {
+                *s = AppState::MainMenu(MainMenu::new());
+            }),
+        ))
+        .boxed(),
+        AppState::Analysis(a) => flex((
+            adapt(analysis(a), |s: &mut AppState, thunk| {
+                thunk.call(s.expect_analysis_mut())
+            }),
+            button("Main menu", |s: &mut AppState| {
+                *s = AppState::MainMenu(MainMenu::new());
+            }),
+        ))
+        .boxed(),
+        AppState::Replay(r) => flex((
+            adapt(replay(r), |s: &mut AppState, thunk| {
+                thunk.call(s.expect_replay_mut())
+            }),
+            button("Main menu", |s: &mut AppState| {
+                *s = AppState::MainMenu(MainMenu::new());
+            }),
+        ))
+        .boxed(),
     }
 }
 
-async fn listen_for_opponent(proxy: MessageProxy<TcpStream>) {
+// The host picks the rules and time control, and tells the other player before the first move.
+// The time control is sent as a length-prefixed string like "300+5", empty for no clock. After
+// that, each move is its coordinate as a byte, then how many milliseconds the player who made it
+// had left afterwards. A player who runs out of time sends `OUT_OF_TIME` in place of a move. Each
+// side flags its own clock, and only flags the other's a few seconds after it's run out, in case
+// their client never says.
+const OUT_OF_TIME: u8 = u8::MAX;
+
+async fn listen_for_opponent(proxy: MessageProxy<(TcpStream, Settings)>, settings: Settings) {
     let listener = tokio::net::TcpListener::bind("0.0.0.0:25567")
         .await
         .unwrap();
-    let (stream, remote_addr) = listener.accept().await.unwrap();
+    let (mut stream, remote_addr) = listener.accept().await.unwrap();
     tracing::info!(?remote_addr, "connected!");
-    let _ = proxy.message(stream);
+    stream.write_u8(settings.ruleset.to_bits()).await.unwrap();
+    let time_control = settings
+        .time_control
+        .map(|time_control| time_control.to_string())
+        .unwrap_or_default();
+    stream.write_u8(time_control.len() as u8).await.unwrap();
+    stream.write_all(time_control.as_bytes()).await.unwrap();
+    let _ = proxy.message((stream, settings));
 }
 
-async fn connect_to_opponent(proxy: MessageProxy<TcpStream>, remote_addr: String) {
-    let stream = tokio::net::TcpStream::connect(&remote_addr).await.unwrap();
+async fn connect_to_opponent(
+    proxy: MessageProxy<Result<(TcpStream, Settings), String>>,
+    remote_addr: String,
+) {
+    let _ = proxy.message(handshake(&remote_addr).await);
+}
+
+/// Connects to the host and reads the settings they sent. The stream is dropped, closing the
+/// connection, if anything goes wrong.
+async fn handshake(remote_addr: &str) -> Result<(TcpStream, Settings), String> {
+    let mut stream = tokio::net::TcpStream::connect(remote_addr)
+        .await
+        .map_err(|e| e.to_string())?;
     tracing::info!(remote_addr, "connected!");
-    let _ = proxy.message(stream);
+    let bits = stream.read_u8().await.map_err(|e| e.to_string())?;
+    let ruleset = Ruleset::from_bits(bits)
+        .ok_or_else(|| format!("the host wants to play with rules we don't know about ({bits})"))?;
+    let len = stream.read_u8().await.map_err(|e| e.to_string())?;
+    let mut time_control = vec![0; len.into()];
+    stream
+        .read_exact(&mut time_control)
+        .await
+        .map_err(|e| e.to_string())?;
+    let time_control = match String::from_utf8_lossy(&time_control).as_ref() {
+        "" => None,
+        time_control => Some(
+            time_control
+                .parse::<TimeControl>()
+                .map_err(|e| format!("the host sent a {e}"))?,
+        ),
+    };
+    tracing::info!(?ruleset, ?time_control, "agreed on rules");
+    let settings = Settings {
+        ruleset,
+        time_control,
+    };
+    Ok((stream, settings))
 }
 
 // The state types differ because we know the state is MainMenu now and all the interesting fields
 // are in there, but we need to be able to change it later
 fn menu(s: &mut MainMenu) -> impl WidgetView<AppState> {
+    let rules = s.ruleset;
+    let geometry = s.geometry;
+    // only hot-seat games can be played on other boards
+    let is_standard = geometry == Geometry::STANDARD;
     let connect_to_game_ui = flex((
         sized_box(textbox(
             s.remote_address.clone(),
             |s: &mut AppState, text| s.expect_main_menu_mut().remote_address = text,
         ))
         .width(160.),
-        button("Connect to game", |s: &mut AppState| {
-            *s = AppState::Connecting(s.expect_main_menu_mut().remote_address.clone());
+        disable_if(
+            !is_standard,
+            button("Connect to game", |s: &mut AppState| {
+                *s = AppState::Connecting(s.expect_main_menu_mut().remote_address.clone());
+            }),
+        ),
+        label(s.connect_error.clone()),
+    ))
+    .direction(Axis::Horizontal);
+    let geometry_ui = flex((
+        label(format!(
+            "Board (other than 3×3 boards two levels deep, local games only): {}",
+            nested_game::describe(geometry)
+        )),
+        flex((
+            label("Size"),
+            slider(
+                geometry.size - 2,
+                Geometry::MAX_SIZE - 2,
+                |s: &mut AppState, i| {
+                    let geometry = &mut s.expect_main_menu_mut().geometry;
+                    geometry.size = i + 2;
+                    geometry.win_length = geometry.win_length.min(geometry.size);
+                    geometry.depth = geometry.depth.min(Geometry::max_depth(geometry.size));
+                },
+            ),
+        ))
+        .direction(Axis::Horizontal),
+        flex((
+            label("Levels"),
+            slider(
+                geometry.depth - 1,
+                Geometry::max_depth(geometry.size) - 1,
+                |s: &mut AppState, i| s.expect_main_menu_mut().geometry.depth = i + 1,
+            ),
+        ))
+        .direction(Axis::Horizontal),
+        flex((
+            label("Line length"),
+            slider(
+                geometry.win_length - 2,
+                geometry.size - 2,
+                |s: &mut AppState, i| s.expect_main_menu_mut().geometry.win_length = i + 2,
+            ),
+        ))
+        .direction(Axis::Horizontal),
+    ));
+    let settings = s.settings();
+    let time_control_ui = flex((
+        label("Clock (like 300+5 or 30/move, empty for none):"),
+        sized_box(textbox(s.time_control.clone(), |s: &mut AppState, text| {
+            s.expect_main_menu_mut().time_control = text
+        }))
+        .width(100.),
+        label(match &settings {
+            Ok(_) => String::new(),
+            Err(e) => e.to_string(),
         }),
     ))
     .direction(Axis::Horizontal);
-    flex((
-        connect_to_game_ui,
-        button("Host game", |s| {
-            *s = AppState::WaitingForOpponent;
+    let rules_ui = flex((
+        label("Rules (the host's are used in network games):"),
+        checkbox(
+            "Keep playing in won minisquares",
+            rules.play_in_won_minisquares,
+            |s: &mut AppState, checked| {
+                s.expect_main_menu_mut().ruleset.play_in_won_minisquares = checked;
+            },
+        ),
+        checkbox(
+            "Sent to a closed minisquare: play in any that isn't full",
+            rules.free_move_anywhere,
+            |s: &mut AppState, checked| {
+                s.expect_main_menu_mut().ruleset.free_move_anywhere = checked;
+            },
+        ),
+        checkbox(
+            "Break draws by number of minisquares won",
+            rules.minisquare_tiebreak,
+            |s: &mut AppState, checked| {
+                s.expect_main_menu_mut().ruleset.minisquare_tiebreak = checked;
+            },
+        ),
+    ));
+    let play_as = format!("Play as {}", s.play_as);
+    let computer_ui = flex((
+        disable_if(
+            settings.is_err() || !is_standard,
+            button("Play vs computer", |s: &mut AppState| {
+                let menu = s.expect_main_menu_mut();
+                let Ok(settings) = menu.settings() else {
+                    return;
+                };
+                let (_, budget) = DIFFICULTIES[menu.difficulty];
+                let opponent = Opponent::Builtin(budget);
+                *s = AppState::InGame(Ultimate::vs_computer(settings, menu.play_as, opponent));
+            }),
+        ),
+        button(play_as, |s: &mut AppState| {
+            let menu = s.expect_main_menu_mut();
+            menu.play_as = !menu.play_as;
+        }),
+        label(DIFFICULTIES[s.difficulty].0),
+        slider(
+            s.difficulty,
+            DIFFICULTIES.len() - 1,
+            |s: &mut AppState, difficulty| s.expect_main_menu_mut().difficulty = difficulty,
+        ),
+    ))
+    .direction(Axis::Horizontal);
+    let engine_ui = flex((
+        sized_box(textbox(
+            s.engine_command.clone(),
+            |s: &mut AppState, text| s.expect_main_menu_mut().engine_command = text,
+        ))
+        .width(160.),
+        disable_if(
+            settings.is_err() || !is_standard || s.engine_command.trim().is_empty(),
+            button("Play vs engine", |s: &mut AppState| {
+                let menu = s.expect_main_menu_mut();
+                let Ok(settings) = menu.settings() else {
+                    return;
+                };
+                let opponent = Opponent::Engine(menu.engine_command.clone());
+                *s = AppState::InGame(Ultimate::vs_computer(settings, menu.play_as, opponent));
+            }),
+        ),
+    ))
+    .direction(Axis::Horizontal);
+    let load_game_ui = flex((
+        sized_box(textbox(s.load_path.clone(), |s: &mut AppState, text| {
+            s.expect_main_menu_mut().load_path = text
+        }))
+        .width(160.),
+        button("Load game", |s: &mut AppState| {
+            let menu = s.expect_main_menu_mut();
+            match save::load(Path::new(&menu.load_path)) {
+                Ok(ult) => *s = AppState::InGame(ult),
+                Err(e) => menu.load_error = e.to_string(),
+            }
+        }),
+        button("Watch replay", |s: &mut AppState| {
+            let menu = s.expect_main_menu_mut();
+            match save::load_position(Path::new(&menu.load_path)) {
+                Ok(position) => *s = AppState::Replay(Replay::new(position)),
+                Err(e) => menu.load_error = e.to_string(),
+            }
         }),
-        button("Start local game", |s| {
-            *s = AppState::InGame(Ultimate::local_multiplayer());
+        button("Analyse", |s: &mut AppState| {
+            let menu = s.expect_main_menu_mut();
+            match save::load_position(Path::new(&menu.load_path)) {
+                Ok(position) => *s = AppState::Analysis(Analysis::new(position)),
+                Err(e) => menu.load_error = e.to_string(),
+            }
         }),
+        label(s.load_error.clone()),
+    ))
+    .direction(Axis::Horizontal);
+    let resume_button = disable_if(
+        !s.can_resume,
+        button(
+            "Resume last game",
+            |s: &mut AppState| match save::load_unfinished_autosave() {
+                Ok(Some(ult)) => *s = AppState::InGame(ult),
+                Ok(None) => s.expect_main_menu_mut().can_resume = false,
+                Err(e) => s.expect_main_menu_mut().load_error = e.to_string(),
+            },
+        ),
+    );
+    flex((
+        resume_button,
+        connect_to_game_ui,
+        disable_if(
+            settings.is_err() || !is_standard,
+            button("Host game", |s: &mut AppState| {
+                if let Ok(settings) = s.expect_main_menu_mut().settings() {
+                    *s = AppState::WaitingForOpponent(settings);
+                }
+            }),
+        ),
+        disable_if(
+            settings.is_err(),
+            button("Start local game", |s: &mut AppState| {
+                let menu = s.expect_main_menu_mut();
+                let Ok(settings) = menu.settings() else {
+                    return;
+                };
+                *s = if menu.geometry == Geometry::STANDARD {
+                    AppState::InGame(Ultimate::local_multiplayer(settings))
+                } else {
+                    let position = NestedPosition::new(menu.geometry, settings.ruleset)
+                        .expect("the menu only allows valid geometries");
+                    AppState::NestedGame(NestedGame::new(position))
+                };
+            }),
+        ),
+        computer_ui,
+        engine_ui,
+        load_game_ui,
+        time_control_ui,
+        rules_ui,
+        geometry_ui,
     ))
     .main_axis_alignment(xilem::view::MainAxisAlignment::Center)
 }
 
 fn game(ult: &mut Ultimate) -> impl WidgetView<Ultimate> {
-    let minisquare = |topleft: usize| {
-        let row = |i| {
-            flex((ult.tile(i), ult.tile(i + 1), ult.tile(i + 2)))
-                .gap(2.)
-                .direction(Axis::Horizontal)
-        };
-        flex((row(topleft), row(topleft + 9), row(topleft + 18))).gap(2.)
-    };
-    let row = |i| {
-        flex((minisquare(i), minisquare(i + 3), minisquare(i + 6)))
-            .gap(4.)
-            .direction(Axis::Horizontal)
+    let board = board(Geometry::STANDARD, |coord| ult.tile(coord));
+    let board = on_shortcut(
+        Shortcut::ctrl('z'),
+        Ultimate::undo,
+        on_shortcut(Shortcut::ctrl_shift('z'), Ultimate::redo, board),
+    );
+    let is_local = ult.is_local_multiplayer();
+    let controls = flex((
+        disable_if(
+            !(is_local && ult.position.can_undo()),
+            button("Undo", Ultimate::undo),
+        ),
+        disable_if(
+            !(is_local && ult.position.can_redo()),
+            button("Redo", Ultimate::redo),
+        ),
+        disable_if(!ult.can_hint(), button("Hint", Ultimate::hint)),
+    ))
+    .direction(Axis::Horizontal);
+    let file_controls = flex((
+        sized_box(textbox(
+            ult.save_path.clone(),
+            |ult: &mut Ultimate, text| {
+                ult.save_path = text;
+            },
+        ))
+        .width(160.),
+        button("Save game", |ult: &mut Ultimate| {
+            ult.file_message = match save::save(Path::new(&ult.save_path), ult) {
+                Ok(()) => format!("Saved to {}", ult.save_path),
+                Err(e) => format!("Couldn't save: {e}"),
+            };
+        }),
+        // the network task would keep feeding the opponent's moves into whatever game replaced
+        // this one
+        disable_if(
+            !is_local,
+            button("Load game", |ult: &mut Ultimate| {
+                match save::load(Path::new(&ult.save_path)) {
+                    // the computer's task is only started when the game view is first shown
+                    Ok(loaded) if loaded.computer.is_some() => {
+                        ult.file_message =
+                            "Games against the computer can only be loaded from the menu"
+                                .to_owned();
+                    }
+                    Ok(mut loaded) => {
+                        // the hint task is already running for this view
+                        std::mem::swap(&mut loaded.hint_send, &mut ult.hint_send);
+                        let save_path = std::mem::take(&mut ult.save_path);
+                        *ult = loaded;
+                        ult.save_path = save_path;
+                    }
+                    Err(e) => ult.file_message = e.to_string(),
+                }
+            }),
+        ),
+        label(ult.file_message.clone()),
+    ))
+    .direction(Axis::Horizontal);
+    // ours at the bottom, or cross's in hot-seat games, where whose is ours changes every move
+    let bottom = if is_local {
+        Player::Cross
+    } else {
+        ult.local_player
     };
-    let ui = flex((row(0), row(27), row(54)))
-        .gap(4.)
-        .main_axis_alignment(xilem::view::MainAxisAlignment::Center);
+    let clocks = flex((
+        label(ult.clock_text(!bottom)),
+        label(ult.clock_text(bottom)),
+    ));
+    let ui = flex((
+        label(ult.status()),
+        flex((board, clocks)).direction(Axis::Horizontal),
+        controls,
+        file_controls,
+        label(ult.rules_description()),
+    ))
+    .main_axis_alignment(xilem::view::MainAxisAlignment::Center);
     let for_recv_task = Mutex::new(ult.for_recv_task.take());
-    fork(
+    let ui = fork(
         ui,
         async_repeat_raw(
             move |_proxy| {
                 if let Some((mut task_rx, mut net_tx)) = for_recv_task.lock().unwrap().take() {
                     Either::Left(async move {
-                        while let Some(coord) = task_rx.recv().await {
+                        while let Some((coord, remaining)) = task_rx.recv().await {
+                            let mut message = [0; 5];
+                            message[0] = coord as u8;
+                            let remaining_ms = remaining.as_millis().min(u32::MAX.into()) as u32;
+                            message[1..].copy_from_slice(&remaining_ms.to_be_bytes());
                             let _ = net_tx
-                                .write_all(&[coord as u8])
+                                .write_all(&message)
                                 .await
                                 .inspect_err(|e| tracing::error!(?e));
                         }
@@ -255,5 +904,27 @@ fn game(ult: &mut Ultimate) -> impl WidgetView<Ultimate> {
             },
             |_s, _msg: ()| unreachable!("the future does not send any messages"),
         ),
+    );
+    // nothing changes by itself without a clock. This is decided once per game rather than on
+    // whether the clock is running, since switching views would restart the network task
+    if ult.clock.is_none() {
+        return ui.boxed();
+    }
+    // redraws the clocks, and notices when someone runs out of time
+    fork(
+        ui,
+        async_repeat(
+            |proxy| async move {
+                let mut interval = tokio::time::interval(Duration::from_millis(100));
+                loop {
+                    interval.tick().await;
+                    if proxy.message(()).is_err() {
+                        break;
+                    }
+                }
+            },
+            |ult: &mut Ultimate, ()| ult.check_clock(),
+        ),
     )
+    .boxed()
 }
@@ -0,0 +1,88 @@
+use xilem::{
+    view::{button, flex, label, Axis},
+    WidgetView,
+};
+
+use ut3_core::{
+    nested::{Geometry, NestedPosition},
+    GameOutcome,
+};
+
+use crate::{
+    disable::disable_if,
+    shortcut::{on_shortcut, Shortcut},
+    tile::{board, tile, PlayTile},
+};
+
+/// A hot-seat game on a board that isn't the standard one. Everything else (network play, saving,
+/// replays) only knows about the standard board, so this is kept separate from `Ultimate`.
+pub struct NestedGame {
+    position: NestedPosition,
+}
+
+impl PlayTile for NestedGame {
+    fn play(&mut self, coord: usize) {
+        if let Err(e) = self.position.try_play(coord) {
+            tracing::error!(coord, %e, "tried to make an illegal move");
+        }
+    }
+}
+
+impl NestedGame {
+    pub fn new(position: NestedPosition) -> Self {
+        NestedGame { position }
+    }
+
+    fn undo(&mut self) {
+        self.position.undo();
+    }
+
+    fn redo(&mut self) {
+        self.position.redo();
+    }
+
+    fn status(&self) -> String {
+        match self.position.outcome() {
+            GameOutcome::Ongoing => format!("{} to move", self.position.side_to_move()),
+            GameOutcome::Win(winner, _) => format!("{winner} wins"),
+            GameOutcome::Draw(_) => "Draw - no moves left".to_owned(),
+        }
+    }
+}
+
+pub fn describe(geometry: Geometry) -> String {
+    let Geometry {
+        size,
+        depth,
+        win_length,
+    } = geometry;
+    let levels = match depth {
+        1 => "one level".to_owned(),
+        depth => format!("{depth} levels"),
+    };
+    format!("{size}×{size} boards, {levels}, {win_length} in a row wins")
+}
+
+pub fn nested_game(g: &mut NestedGame) -> impl WidgetView<NestedGame> {
+    let position = &g.position;
+    let board = board(position.geometry(), |coord| {
+        tile(coord, position.tile(coord), position.is_legal(coord))
+    });
+    let board = on_shortcut(
+        Shortcut::ctrl('z'),
+        NestedGame::undo,
+        on_shortcut(Shortcut::ctrl_shift('z'), NestedGame::redo, board),
+    );
+    let controls = flex((
+        disable_if(!position.can_undo(), button("Undo", NestedGame::undo)),
+        disable_if(!position.can_redo(), button("Redo", NestedGame::redo)),
+    ))
+    .direction(Axis::Horizontal);
+    flex((
+        label(g.status()),
+        board,
+        controls,
+        label(describe(position.geometry())),
+    ))
+    .main_axis_alignment(xilem::view::MainAxisAlignment::Center)
+}
@@ -0,0 +1,204 @@
+use std::time::Duration;
+
+use xilem::{
+    core::fork,
+    view::{async_repeat, button, flex, label, Axis},
+    WidgetView,
+};
+
+use ut3_core::{nested::Geometry, notation::Global, GameOutcome, Player, Position};
+
+use crate::{
+    disable::disable_if,
+    slider::slider,
+    tile::{board, tile, PlayTile},
+};
+
+/// How long autoplay waits between moves at each speed, slowest first.
+const SPEEDS: [Duration; 5] = [
+    Duration::from_millis(3000),
+    Duration::from_millis(1500),
+    Duration::from_millis(800),
+    Duration::from_millis(400),
+    Duration::from_millis(200),
+];
+
+const TICK: Duration = Duration::from_millis(100);
+
+/// Stepping through a game that's already been played, without being able to change it.
+pub struct Replay {
+    /// The game as of the move being shown, with the rest of it waiting to be redone.
+    position: Position,
+    length: usize,
+    autoplay: bool,
+    /// An index into `SPEEDS`.
+    speed: usize,
+    since_last_move: Duration,
+}
+
+// the tiles are never playable
+impl PlayTile for Replay {
+    fn play(&mut self, _: usize) {}
+}
+
+impl Replay {
+    /// Starts at the beginning of the game that led to `position`.
+    pub fn new(mut position: Position) -> Self {
+        let length = position.history().len();
+        while position.undo().is_some() {}
+        Replay {
+            position,
+            length,
+            autoplay: false,
+            speed: SPEEDS.len() / 2,
+            since_last_move: Duration::ZERO,
+        }
+    }
+
+    /// Starts at the end of the game that led to `position`.
+    pub fn at_end(position: Position) -> Self {
+        let mut replay = Replay::new(position);
+        replay.go_to(replay.length);
+        replay
+    }
+
+    /// The game as of the move being shown.
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    /// How many moves into the game we are.
+    pub fn ply(&self) -> usize {
+        self.position.history().len()
+    }
+
+    fn go_to(&mut self, ply: usize) {
+        while self.ply() > ply && self.position.undo().is_some() {}
+        while self.ply() < ply && self.position.redo().is_some() {}
+    }
+
+    fn first(&mut self) {
+        self.autoplay = false;
+        self.go_to(0);
+    }
+
+    fn previous(&mut self) {
+        self.autoplay = false;
+        self.go_to(self.ply().saturating_sub(1));
+    }
+
+    fn next(&mut self) {
+        self.go_to(self.ply() + 1);
+    }
+
+    fn last(&mut self) {
+        self.autoplay = false;
+        self.go_to(self.length);
+    }
+
+    fn toggle_autoplay(&mut self) {
+        self.autoplay = !self.autoplay;
+        self.since_last_move = Duration::ZERO;
+        // play from the start again if we're already at the end
+        if self.autoplay && self.ply() == self.length {
+            self.go_to(0);
+        }
+    }
+
+    fn tick(&mut self) {
+        if !self.autoplay {
+            return;
+        }
+        self.since_last_move += TICK;
+        if self.since_last_move >= SPEEDS[self.speed] {
+            self.since_last_move = Duration::ZERO;
+            self.next();
+            self.autoplay = self.ply() < self.length;
+        }
+    }
+
+    pub fn status(&self) -> String {
+        let Some(&coord) = self.position.history().last() else {
+            return format!("Start of game ({} moves)", self.length);
+        };
+        let player = self.position.tile(coord).unwrap_or(Player::Cross);
+        let mut status = format!(
+            "Move {} of {}: {player} played {}",
+            self.ply(),
+            self.length,
+            Global(coord)
+        );
+        match self.position.outcome() {
+            GameOutcome::Ongoing => {}
+            GameOutcome::Win(winner, _) => status.push_str(&format!(", {winner} wins")),
+            GameOutcome::Draw(_) => status.push_str(", draw"),
+        }
+        status
+    }
+}
+
+pub fn replay(r: &mut Replay) -> impl WidgetView<Replay> {
+    let board = board(Geometry::STANDARD, |coord| {
+        tile(coord, r.position.tile(coord), false)
+    });
+    flex((label(r.status()), board, controls(r)))
+        .main_axis_alignment(xilem::view::MainAxisAlignment::Center)
+}
+
+/// The move slider, step buttons and autoplay, for any view that steps through a game.
+pub fn controls(r: &mut Replay) -> impl WidgetView<Replay> {
+    let at_start = r.ply() == 0;
+    let at_end = r.ply() == r.length;
+    let buttons = flex((
+        disable_if(at_start, button("|<", Replay::first)),
+        disable_if(at_start, button("<", Replay::previous)),
+        button(
+            if r.autoplay { "Pause" } else { "Play" },
+            Replay::toggle_autoplay,
+        ),
+        disable_if(
+            at_end,
+            button(">", |r: &mut Replay| {
+                r.autoplay = false;
+                r.next();
+            }),
+        ),
+        disable_if(at_end, button(">|", Replay::last)),
+    ))
+    .direction(Axis::Horizontal);
+    let speed = flex((
+        label("Speed"),
+        slider(r.speed, SPEEDS.len() - 1, |r: &mut Replay, speed| {
+            r.speed = speed;
+        }),
+    ))
+    .direction(Axis::Horizontal);
+    let ui = flex((
+        slider(r.ply(), r.length, |r: &mut Replay, ply| {
+            r.autoplay = false;
+            r.go_to(ply);
+        }),
+        buttons,
+        speed,
+    ));
+    // the ticker only runs while autoplay is on, since nothing else moves by itself
+    if !r.autoplay {
+        return ui.boxed();
+    }
+    fork(
+        ui,
+        async_repeat(
+            |proxy| async move {
+                let mut interval = tokio::time::interval(TICK);
+                loop {
+                    interval.tick().await;
+                    if proxy.message(()).is_err() {
+                        break;
+                    }
+                }
+            },
+            |r: &mut Replay, ()| r.tick(),
+        ),
+    )
+    .boxed()
+}
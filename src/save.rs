@@ -0,0 +1,212 @@
+//! Saving games to files as [game records](ut3_core::record), and loading them back.
+//!
+//! Saved games are dated, and the players are named after the local user, the computer or the
+//! network opponent.
+//!
+//! Besides the standard headers, games against the computer get a `Computer` header saying which
+//! side it plays and an `Opponent` header saying what it is, and games with a clock get a
+//! `TimeLeft` header with cross's and nought's time in milliseconds, like `[TimeLeft "81200
+//! 95000"]`.
+
+use std::{
+    fmt, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use ut3_core::{
+    record::{GameRecord, RecordError},
+    Player, Position,
+};
+
+use crate::{computer::Opponent, Ultimate};
+
+/// The file games are saved to and loaded from unless the player picks another.
+pub const DEFAULT_PATH: &str = "game.ut3";
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    Record(RecordError),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "couldn't read the file: {e}"),
+            LoadError::Record(e) => write!(f, "not a valid game: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/// Writes to a temporary file next to `path` and renames it over the top, so a crash part way
+/// through leaves the old save intact rather than a half-written one.
+pub fn save(path: &Path, ult: &Ultimate) -> io::Result<()> {
+    let mut record = GameRecord::from_position(&ult.position);
+    record.date = Some(today());
+    let local = local_name();
+    let name = |player: Player| match &ult.computer {
+        Some(computer) if computer.player == player => computer.opponent.name(),
+        _ if ult.send.is_some() && player != ult.local_player => "Network opponent".to_owned(),
+        // both sides in hot-seat games
+        _ => local.clone(),
+    };
+    record.cross = Some(name(Player::Cross));
+    record.nought = Some(name(Player::Nought));
+    if let Some(clock) = &ult.clock {
+        let now = Instant::now();
+        let [cross, nought] =
+            [Player::Cross, Player::Nought].map(|player| clock.remaining(player, now).as_millis());
+        record.time_control = Some(clock.control().to_string());
+        let time_left = format!("{cross} {nought}");
+        record
+            .other_headers
+            .push(("TimeLeft".to_owned(), time_left));
+    }
+    if let Some(computer) = &ult.computer {
+        let headers = [
+            ("Computer", computer.player.to_string()),
+            ("Opponent", computer.opponent.to_string()),
+        ];
+        for (name, value) in headers {
+            record.other_headers.push((name.to_owned(), value));
+        }
+    }
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let mut file = fs::File::create(&temp_path)?;
+    file.write_all(record.to_string().as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&temp_path, path)
+}
+
+/// Whoever is logged in, for naming the local side.
+fn local_name() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "Local player".to_owned())
+}
+
+/// Today's date in UTC, written `YYYY.MM.DD` for the `Date` header.
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() / (24 * 60 * 60));
+    // Howard Hinnant's days_from_civil in reverse, with years starting in March so leap days come
+    // last
+    let days = days + 719_468;
+    let (era, day_of_era) = (days / 146_097, days % 146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = (month_from_march + 2) % 12 + 1;
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    format!("{year:04}.{month:02}.{day:02}")
+}
+
+/// Loads a game to carry on with, against the computer if it was saved from a game against the
+/// computer and hot-seat otherwise, so finished network games can be stepped through with undo
+/// and redo.
+pub fn load(path: &Path) -> Result<Ultimate, LoadError> {
+    let record = load_record(path)?;
+    let position = record.replay().map_err(LoadError::Record)?;
+    let header = |name: &str| {
+        record
+            .other_headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    };
+    let bad = |name: &str, value: &str| {
+        LoadError::Record(RecordError::BadHeaderValue {
+            name: name.to_owned(),
+            value: value.to_owned(),
+        })
+    };
+
+    let time_control = match &record.time_control {
+        Some(time_control) => Some(
+            time_control
+                .parse()
+                .map_err(|_| bad("TimeControl", time_control))?,
+        ),
+        None => None,
+    };
+    let computer = match (header("Computer"), header("Opponent")) {
+        (Some(player), Some(opponent)) => {
+            let player = match player {
+                "Cross" => Player::Cross,
+                "Nought" => Player::Nought,
+                _ => return Err(bad("Computer", player)),
+            };
+            let opponent: Opponent = opponent.parse().map_err(|_| bad("Opponent", opponent))?;
+            Some((player, opponent))
+        }
+        _ => None,
+    };
+    let time_left = match header("TimeLeft") {
+        Some(time_left) => {
+            let ms: Vec<u64> = time_left
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .map_err(|_| bad("TimeLeft", time_left))?;
+            let [cross, nought] = ms[..] else {
+                return Err(bad("TimeLeft", time_left));
+            };
+            Some([(Player::Cross, cross), (Player::Nought, nought)])
+        }
+        None => None,
+    };
+
+    let mut ult = Ultimate::resume(position, time_control, computer);
+    if let (Some(clock), Some(time_left)) = (&mut ult.clock, time_left) {
+        let now = Instant::now();
+        for (player, ms) in time_left {
+            clock.set_remaining(player, Duration::from_millis(ms), now);
+        }
+    }
+    Ok(ult)
+}
+
+fn load_record(path: &Path) -> Result<GameRecord, LoadError> {
+    let text = fs::read_to_string(path).map_err(LoadError::Io)?;
+    text.parse().map_err(LoadError::Record)
+}
+
+/// Loads the position at the end of a saved game, with the moves that led to it.
+pub fn load_position(path: &Path) -> Result<Position, LoadError> {
+    load_record(path)?.replay().map_err(LoadError::Record)
+}
+
+/// Where the game in progress is kept, in the per-user data directory, e.g.
+/// `~/.local/share/ut3/autosave.ut3` on Linux.
+fn autosave_path() -> Option<PathBuf> {
+    Some(dirs::data_dir()?.join("ut3").join("autosave.ut3"))
+}
+
+/// Saves the game in progress so it survives the app crashing. Network games aren't saved, since
+/// there'd be no opponent to carry on with.
+pub fn autosave(ult: &Ultimate) -> io::Result<()> {
+    if ult.send.is_some() {
+        return Ok(());
+    }
+    let path = autosave_path()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "couldn't find a data directory"))?;
+    fs::create_dir_all(path.parent().unwrap())?;
+    save(&path, ult)
+}
+
+/// The autosaved game, if there is one and it hasn't finished.
+pub fn load_unfinished_autosave() -> Result<Option<Ultimate>, LoadError> {
+    let Some(path) = autosave_path().filter(|path| path.exists()) else {
+        return Ok(None);
+    };
+    let ult = load(&path)?;
+    Ok((!ult.position.outcome().is_over()).then_some(ult))
+}
@@ -0,0 +1,187 @@
+use accesskit::Role;
+use masonry::{
+    vello::Scene, AccessCtx, AccessEvent, Action, BoxConstraints, EventCtx, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, Point, PointerEvent, Size, StatusChange, TextEvent, Widget, WidgetId,
+    WidgetPod,
+};
+use smallvec::SmallVec;
+use winit::{
+    event::ElementState,
+    keyboard::{Key, ModifiersState},
+};
+use xilem::{
+    core::{Message, MessageResult, Mut, View, ViewId, ViewPathTracker},
+    Pod, ViewCtx, WidgetView,
+};
+
+/// A key combination like Ctrl+Shift+Z.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Shortcut {
+    pub key: char,
+    pub ctrl: bool,
+    pub shift: bool,
+}
+
+impl Shortcut {
+    pub const fn ctrl(key: char) -> Self {
+        Shortcut {
+            key,
+            ctrl: true,
+            shift: false,
+        }
+    }
+
+    pub const fn ctrl_shift(key: char) -> Self {
+        Shortcut {
+            key,
+            ctrl: true,
+            shift: true,
+        }
+    }
+
+    fn matches(&self, key: &Key, modifiers: ModifiersState) -> bool {
+        let Key::Character(c) = key else {
+            return false;
+        };
+        c.eq_ignore_ascii_case(self.key.encode_utf8(&mut [0; 4]))
+            && modifiers.control_key() == self.ctrl
+            && modifiers.shift_key() == self.shift
+    }
+}
+
+/// Calls `callback` when `shortcut` is pressed while `inner` (or anything in it) has focus.
+/// Clicking anywhere inside `inner` focuses it.
+pub fn on_shortcut<V, F>(shortcut: Shortcut, callback: F, inner: V) -> OnShortcut<V, F> {
+    OnShortcut(shortcut, callback, inner)
+}
+
+pub struct OnShortcut<V, F>(Shortcut, F, V);
+
+// the child gets its own id so that its actions can't be mistaken for ours
+fn child_id() -> ViewId {
+    ViewId::new(0)
+}
+
+struct ShortcutPressed;
+
+impl<T, A, V, F> View<T, A, ViewCtx> for OnShortcut<V, F>
+where
+    V: WidgetView<T, A>,
+    F: Fn(&mut T) + 'static,
+{
+    type Element = Pod<OnShortcutWidget>;
+    type ViewState = V::ViewState;
+
+    fn build(&self, ctx: &mut ViewCtx) -> (Self::Element, Self::ViewState) {
+        let (child, child_state) = ctx.with_id(child_id(), |ctx| self.2.build(ctx));
+        let element =
+            ctx.with_action_widget(|_| Pod::new(OnShortcutWidget(self.0, child.inner.boxed())));
+        (element, child_state)
+    }
+
+    fn rebuild<'el>(
+        &self,
+        prev: &Self,
+        view_state: &mut Self::ViewState,
+        ctx: &mut ViewCtx,
+        mut element: Mut<'el, Self::Element>,
+    ) -> Mut<'el, Self::Element> {
+        element.widget.0 = self.0;
+        {
+            let mut child = element.ctx.get_mut(&mut element.widget.1);
+            ctx.with_id(child_id(), |ctx| {
+                self.2.rebuild(&prev.2, view_state, ctx, child.downcast());
+            });
+        }
+        element
+    }
+
+    fn teardown(
+        &self,
+        view_state: &mut Self::ViewState,
+        ctx: &mut ViewCtx,
+        mut element: Mut<'_, Self::Element>,
+    ) {
+        let mut child = element.ctx.get_mut(&mut element.widget.1);
+        ctx.with_id(child_id(), |ctx| {
+            self.2.teardown(view_state, ctx, child.downcast())
+        });
+    }
+
+    fn message(
+        &self,
+        view_state: &mut Self::ViewState,
+        id_path: &[ViewId],
+        message: Box<dyn Message>,
+        app_state: &mut T,
+    ) -> MessageResult<A, Box<dyn Message>> {
+        if let Some((first, rest)) = id_path.split_first() {
+            assert_eq!(*first, child_id());
+            return self.2.message(view_state, rest, message, app_state);
+        }
+        let action = message.downcast::<Action>().unwrap();
+        let Action::Other(payload) = *action else {
+            panic!()
+        };
+        assert!(payload.is::<ShortcutPressed>());
+        (self.1)(app_state);
+        MessageResult::RequestRebuild
+    }
+}
+
+pub struct OnShortcutWidget(Shortcut, WidgetPod<Box<dyn Widget>>);
+
+impl Widget for OnShortcutWidget {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        if let PointerEvent::PointerDown(_, _) = event {
+            ctx.request_focus();
+        }
+        self.1.on_pointer_event(ctx, event)
+    }
+
+    fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        if let TextEvent::KeyboardKey(key, modifiers) = event {
+            if key.state == ElementState::Pressed && self.0.matches(&key.logical_key, *modifiers) {
+                ctx.submit_action(Action::Other(Box::new(ShortcutPressed)));
+                ctx.set_handled();
+                return;
+            }
+        }
+        self.1.on_text_event(ctx, event)
+    }
+
+    fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
+        self.1.on_access_event(ctx, event)
+    }
+
+    fn on_status_change(&mut self, _: &mut LifeCycleCtx, _: &StatusChange) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        if let LifeCycle::BuildFocusChain = event {
+            ctx.register_for_focus();
+        }
+        self.1.lifecycle(ctx, event)
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        let size = self.1.layout(ctx, bc);
+        ctx.place_child(&mut self.1, Point::new(0., 0.));
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        self.1.paint(ctx, scene)
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::GenericContainer
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        self.1.accessibility(ctx)
+    }
+
+    fn children_ids(&self) -> SmallVec<[WidgetId; 16]> {
+        smallvec::smallvec![self.1.id()]
+    }
+}
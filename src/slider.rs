@@ -0,0 +1,194 @@
+use accesskit::Role;
+use masonry::{
+    kurbo::{Circle, RoundedRect},
+    vello::{peniko::Fill, Scene},
+    AccessCtx, AccessEvent, Action, Affine, BoxConstraints, EventCtx, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, Point, PointerEvent, Size, StatusChange, TextEvent, Widget, WidgetId,
+};
+use smallvec::SmallVec;
+use xilem::{
+    core::{Message, MessageResult, Mut, View, ViewId},
+    Color, Pod, ViewCtx,
+};
+
+/// A horizontal slider picking a whole number from `0..=max`. `on_change` is called as it's
+/// dragged.
+pub fn slider<F>(value: usize, max: usize, on_change: F) -> Slider<F> {
+    Slider {
+        value,
+        max,
+        on_change,
+    }
+}
+
+pub struct Slider<F> {
+    value: usize,
+    max: usize,
+    on_change: F,
+}
+
+struct SliderMoved(usize);
+
+impl<T, A, F> View<T, A, ViewCtx> for Slider<F>
+where
+    F: Fn(&mut T, usize) + 'static,
+{
+    type Element = Pod<SliderWidget>;
+    type ViewState = ();
+
+    fn build(&self, ctx: &mut ViewCtx) -> (Self::Element, Self::ViewState) {
+        ctx.with_leaf_action_widget(|_| {
+            Pod::new(SliderWidget {
+                value: self.value,
+                max: self.max,
+            })
+        })
+    }
+
+    fn rebuild<'el>(
+        &self,
+        prev: &Self,
+        _: &mut Self::ViewState,
+        _: &mut ViewCtx,
+        mut element: Mut<'el, Self::Element>,
+    ) -> Mut<'el, Self::Element> {
+        if (self.value, self.max) != (prev.value, prev.max) {
+            element.widget.value = self.value;
+            element.widget.max = self.max;
+            element.ctx.request_paint();
+        }
+        element
+    }
+
+    fn teardown(&self, _: &mut Self::ViewState, _: &mut ViewCtx, _: Mut<'_, Self::Element>) {}
+
+    fn message(
+        &self,
+        _: &mut Self::ViewState,
+        id_path: &[ViewId],
+        message: Box<dyn Message>,
+        app_state: &mut T,
+    ) -> MessageResult<A, Box<dyn Message>> {
+        assert_eq!(id_path, &[]);
+        let action = message.downcast::<Action>().unwrap();
+        let Action::Other(payload) = *action else {
+            panic!()
+        };
+        let SliderMoved(value) = *payload.downcast::<SliderMoved>().unwrap();
+        (self.on_change)(app_state, value);
+        MessageResult::RequestRebuild
+    }
+}
+
+const WIDTH: f64 = 240.;
+const HEIGHT: f64 = 20.;
+const THUMB_RADIUS: f64 = 7.;
+
+pub struct SliderWidget {
+    value: usize,
+    max: usize,
+}
+
+impl SliderWidget {
+    /// Where the thumb is drawn for `value`, leaving room for it at either end.
+    fn thumb_x(&self, width: f64) -> f64 {
+        let fraction = if self.max == 0 {
+            0.
+        } else {
+            self.value as f64 / self.max as f64
+        };
+        THUMB_RADIUS + fraction * (width - 2. * THUMB_RADIUS)
+    }
+
+    fn value_at(&self, x: f64, width: f64) -> usize {
+        let fraction = (x - THUMB_RADIUS) / (width - 2. * THUMB_RADIUS);
+        (fraction.clamp(0., 1.) * self.max as f64).round() as usize
+    }
+
+    fn drag_to(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        let position = event.pointer_state().position;
+        let x = position.x - ctx.window_origin().x;
+        let value = self.value_at(x, ctx.size().width);
+        if value != self.value {
+            self.value = value;
+            ctx.submit_action(Action::Other(Box::new(SliderMoved(value))));
+            ctx.request_paint();
+        }
+    }
+}
+
+impl Widget for SliderWidget {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        match event {
+            PointerEvent::PointerDown(_, _) => {
+                ctx.set_active(true);
+                self.drag_to(ctx, event);
+            }
+            PointerEvent::PointerMove(_) if ctx.is_active() => self.drag_to(ctx, event),
+            PointerEvent::PointerUp(_, _) => ctx.set_active(false),
+            _ => {}
+        }
+    }
+
+    fn on_text_event(&mut self, _: &mut EventCtx, _: &TextEvent) {}
+
+    fn on_access_event(&mut self, _: &mut EventCtx, _: &AccessEvent) {}
+
+    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, event: &StatusChange) {
+        if let StatusChange::HotChanged(_) = event {
+            ctx.request_paint();
+        }
+    }
+
+    fn lifecycle(&mut self, _: &mut LifeCycleCtx, _: &LifeCycle) {}
+
+    fn layout(&mut self, _: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        bc.constrain((WIDTH, HEIGHT))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        let (w, h) = ctx.size().into();
+        let x = self.thumb_x(w);
+        let track = |from: f64, to: f64| RoundedRect::new(from, h / 2. - 2., to, h / 2. + 2., 2.);
+        scene.fill(
+            Fill::NonZero,
+            Affine::IDENTITY,
+            Color::rgb8(40, 40, 40),
+            None,
+            &track(THUMB_RADIUS, w - THUMB_RADIUS),
+        );
+        scene.fill(
+            Fill::NonZero,
+            Affine::IDENTITY,
+            Color::rgb8(120, 120, 120),
+            None,
+            &track(THUMB_RADIUS, x),
+        );
+        let thumb_colour = if ctx.is_hot() || ctx.is_active() {
+            Color::rgb8(220, 220, 220)
+        } else {
+            Color::rgb8(170, 170, 170)
+        };
+        scene.fill(
+            Fill::NonZero,
+            Affine::IDENTITY,
+            thumb_colour,
+            None,
+            &Circle::new(Point::new(x, h / 2.), THUMB_RADIUS),
+        );
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::Slider
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        ctx.current_node().set_numeric_value(self.value as f64);
+        ctx.current_node().set_min_numeric_value(0.);
+        ctx.current_node().set_max_numeric_value(self.max as f64);
+    }
+
+    fn children_ids(&self) -> SmallVec<[WidgetId; 16]> {
+        SmallVec::new_const()
+    }
+}
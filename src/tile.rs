@@ -7,26 +7,89 @@ use masonry::{
 use smallvec::SmallVec;
 use xilem::{
     core::{Message, MessageResult, ViewId},
-    Color, Pod, ViewCtx,
+    view::{flex, Axis},
+    AnyWidgetView, Color, Pod, ViewCtx, WidgetView,
 };
 
-use crate::Player;
+use ut3_core::{nested::Geometry, Player};
+
+/// App state that has a board of [`Tile`]s in it.
+pub trait PlayTile {
+    /// Called when a playable tile is clicked.
+    fn play(&mut self, coord: usize);
+}
+
+impl PlayTile for crate::Ultimate {
+    fn play(&mut self, coord: usize) {
+        self.make_move(coord);
+    }
+}
+
+/// The whole board, with wider gaps between bigger boards.
+pub fn board<T: PlayTile + 'static>(
+    geometry: Geometry,
+    tile: impl Fn(usize) -> Tile,
+) -> Box<AnyWidgetView<T>> {
+    nested_board(geometry, &[], &tile)
+}
+
+fn nested_board<T: PlayTile + 'static>(
+    geometry: Geometry,
+    path: &[usize],
+    tile: &dyn Fn(usize) -> Tile,
+) -> Box<AnyWidgetView<T>> {
+    if path.len() == geometry.depth {
+        return tile(geometry.coord_of_path(path)).boxed();
+    }
+    let gap = 2. * (geometry.depth - path.len()) as f64;
+    let cell = |cell| nested_board(geometry, &[path, &[cell]].concat(), tile);
+    let rows: Vec<_> = (0..geometry.size)
+        .map(|row| {
+            let cells: Vec<_> = (0..geometry.size)
+                .map(|column| cell(row * geometry.size + column))
+                .collect();
+            flex(cells).gap(gap).direction(Axis::Horizontal)
+        })
+        .collect();
+    flex(rows).gap(gap).boxed()
+}
 
 pub fn tile(coord: usize, content: Option<Player>, is_playable: bool) -> Tile {
     Tile {
         coord,
         content,
         is_playable,
+        highlight: Highlight::None,
     }
 }
 
+/// Something drawn over a tile to draw attention to it.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Highlight {
+    #[default]
+    None,
+    /// The move the engine suggests.
+    Hint,
+    /// How good a move is for the player making it, from 0 (losing) to 1 (winning), shown by
+    /// colouring the whole tile.
+    Heat(f32),
+}
+
 pub struct Tile {
     coord: usize,
     content: Option<Player>,
     is_playable: bool,
+    highlight: Highlight,
 }
 
-impl<A> xilem::core::View<crate::Ultimate, A, ViewCtx> for Tile {
+impl Tile {
+    pub fn highlight(mut self, highlight: Highlight) -> Self {
+        self.highlight = highlight;
+        self
+    }
+}
+
+impl<T: PlayTile, A> xilem::core::View<T, A, ViewCtx> for Tile {
     type Element = Pod<TileWidget>;
     type ViewState = ();
 
@@ -35,6 +98,7 @@ impl<A> xilem::core::View<crate::Ultimate, A, ViewCtx> for Tile {
             Pod::new(TileWidget {
                 content: self.content,
                 is_playable: self.is_playable,
+                highlight: self.highlight,
             })
         })
     }
@@ -55,6 +119,10 @@ impl<A> xilem::core::View<crate::Ultimate, A, ViewCtx> for Tile {
             e.widget.is_playable = self.is_playable;
             e.ctx.request_paint();
         }
+        if self.highlight != prev.highlight {
+            e.widget.highlight = self.highlight;
+            e.ctx.request_paint();
+        }
         e
     }
 
@@ -71,7 +139,7 @@ impl<A> xilem::core::View<crate::Ultimate, A, ViewCtx> for Tile {
         _: &mut Self::ViewState,
         id: &[ViewId],
         message: Box<dyn Message>,
-        ult: &mut crate::Ultimate,
+        app_state: &mut T,
     ) -> MessageResult<A, Box<dyn Message>> {
         assert_eq!(id, &[]);
         let action = message.downcast::<Action>().unwrap();
@@ -79,7 +147,7 @@ impl<A> xilem::core::View<crate::Ultimate, A, ViewCtx> for Tile {
             panic!()
         };
         if button == PointerButton::Primary && self.is_playable {
-            ult.make_move(self.coord);
+            app_state.play(self.coord);
             return MessageResult::RequestRebuild;
         }
         return MessageResult::Nop;
@@ -89,6 +157,38 @@ impl<A> xilem::core::View<crate::Ultimate, A, ViewCtx> for Tile {
 pub struct TileWidget {
     content: Option<Player>,
     is_playable: bool,
+    highlight: Highlight,
+}
+
+/// From red for 0, through grey, to green for 1.
+fn heat_colour(heat: f32) -> Color {
+    let heat = heat.clamp(0., 1.) as f64;
+    let (bad, middle, good) = ([170., 40., 40.], [70., 70., 70.], [40., 150., 60.]);
+    let (from, to, t) = if heat < 0.5 {
+        (bad, middle, heat * 2.)
+    } else {
+        (middle, good, heat * 2. - 1.)
+    };
+    let channel = |i: usize| (from[i] + (to[i] - from[i]) * t).round() as u8;
+    Color::rgb8(channel(0), channel(1), channel(2))
+}
+
+impl TileWidget {
+    fn paint_highlight(&self, scene: &mut Scene, w: f64, h: f64) {
+        match self.highlight {
+            Highlight::None | Highlight::Heat(_) => {}
+            Highlight::Hint => {
+                let ring = RoundedRect::new(1.5, 1.5, w - 1.5, h - 1.5, 3.);
+                scene.stroke(
+                    &masonry::kurbo::Stroke::new(3.),
+                    Affine::IDENTITY,
+                    Color::GOLD,
+                    None,
+                    &ring,
+                );
+            }
+        }
+    }
 }
 
 impl masonry::Widget for TileWidget {
@@ -125,7 +225,9 @@ impl masonry::Widget for TileWidget {
 
     fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
         let (w, h) = ctx.size().into();
-        let colour = if !self.is_playable {
+        let colour = if let Highlight::Heat(heat) = self.highlight {
+            heat_colour(heat)
+        } else if !self.is_playable {
             Color::rgb8(26, 26, 26)
         } else if ctx.is_hot() {
             Color::rgb8(80, 80, 80)
@@ -140,6 +242,7 @@ impl masonry::Widget for TileWidget {
             None,
             &rect,
         );
+        self.paint_highlight(scene, w, h);
 
         if self.content == Some(Player::Nought) {
             let nought = masonry::kurbo::Circle::new((12., 12.), 8.);
@@ -0,0 +1,62 @@
+//! Feeds arbitrary bytes into the move API: the first picks the rules, and the rest are tile
+//! coordinates to play, with a few values set aside for undo and redo. Afterwards the bitboard
+//! and the position strings have to agree with where `Position` ended up.
+//!
+//! Run with `cargo fuzz run moves` from `ut3-core`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ut3_core::{BitPosition, Position, Ruleset};
+
+const UNDO: u8 = 0xff;
+const REDO: u8 = 0xfe;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&rules, moves)) = data.split_first() else {
+        return;
+    };
+    let Some(rules) = Ruleset::from_bits(rules) else {
+        return;
+    };
+    let mut position = Position::with_rules(rules);
+    for &byte in moves {
+        match byte {
+            UNDO => {
+                let can_undo = position.can_undo();
+                assert_eq!(position.undo().is_some(), can_undo);
+            }
+            REDO => {
+                let can_redo = position.can_redo();
+                assert_eq!(position.redo().is_some(), can_redo);
+            }
+            coord => {
+                let coord = coord as usize;
+                let before = position.clone();
+                match position.try_play(coord) {
+                    Ok(_) => assert!(before.is_legal(coord)),
+                    Err(_) => {
+                        assert!(!before.is_legal(coord));
+                        assert_eq!(position, before);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut bits = BitPosition::with_rules(rules);
+    for &coord in position.history() {
+        assert!(bits.is_legal(coord));
+        bits.make(coord);
+    }
+    assert_eq!(bits, BitPosition::from(&position));
+    assert!(bits
+        .legal_moves()
+        .iter()
+        .map(|&c| c as usize)
+        .eq(position.legal_moves()));
+    assert_eq!(bits.outcome(), position.outcome());
+
+    let parsed = Position::from_fen(&position.to_string(), rules).unwrap();
+    assert_eq!(parsed, position);
+});
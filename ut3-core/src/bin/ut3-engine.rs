@@ -0,0 +1,35 @@
+//! The built-in search, speaking the [engine protocol](ut3_core::engine) on stdin and stdout.
+//!
+//! ```text
+//! ut3-engine
+//! ```
+//!
+//! Lines that can't be understood are reported on stderr and otherwise ignored.
+
+use std::io::{self, BufRead, Write};
+
+use ut3_core::engine::Engine;
+
+fn main() -> io::Result<()> {
+    let mut engine = Engine::new("ut3-engine");
+    let mut stdout = io::stdout().lock();
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match engine.handle_line(&line) {
+            Ok(responses) => {
+                for response in responses {
+                    writeln!(stdout, "{response}")?;
+                }
+                stdout.flush()?;
+            }
+            Err(e) => eprintln!("{e}"),
+        }
+        if line.split_whitespace().next() == Some("quit") {
+            break;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,112 @@
+//! Counts the move sequences of a given length, to check the move generator.
+//!
+//! ```text
+//! ut3-perft [--divide] [--reference] [--rules RULES] DEPTH [POSITION]
+//! ```
+//!
+//! `POSITION` is a [position string](ut3_core::fen) and defaults to the empty board. `RULES` is a
+//! comma-separated list like `play-in-won,minisquare-tiebreak`. `--divide` prints the count under
+//! each first move, and `--reference` uses the slow but simple `Position` instead of the bitboard.
+
+use std::{process::ExitCode, time::Instant};
+
+use ut3_core::{
+    fen::STARTPOS,
+    notation::Global,
+    perft::{divide, divide_reference, perft, perft_reference},
+    BitPosition, Position, Ruleset,
+};
+
+const USAGE: &str = "usage: ut3-perft [--divide] [--reference] [--rules RULES] DEPTH [POSITION]";
+
+struct Args {
+    divide: bool,
+    reference: bool,
+    rules: Ruleset,
+    depth: u32,
+    position: String,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = std::env::args().skip(1);
+    let mut divide = false;
+    let mut reference = false;
+    let mut rules = Ruleset::STANDARD;
+    let mut positional = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--divide" => divide = true,
+            "--reference" => reference = true,
+            "--rules" => {
+                let list = args.next().ok_or("--rules needs a value")?;
+                rules = list.parse().map_err(|e| format!("{e}"))?;
+            }
+            "-h" | "--help" => return Err(USAGE.to_owned()),
+            _ => positional.push(arg),
+        }
+    }
+    let mut positional = positional.into_iter();
+    let depth = positional
+        .next()
+        .ok_or(USAGE)?
+        .parse()
+        .map_err(|e| format!("bad depth: {e}"))?;
+    // a position string has spaces in, so the rest of the arguments can be one or several
+    let position = positional.collect::<Vec<_>>().join(" ");
+    let position = if position.is_empty() {
+        STARTPOS.to_owned()
+    } else {
+        position
+    };
+    Ok(Args {
+        divide,
+        reference,
+        rules,
+        depth,
+        position,
+    })
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut position = match Position::from_fen(&args.position, args.rules) {
+        Ok(position) => position,
+        Err(e) => {
+            eprintln!("bad position: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut bits = BitPosition::from(&position);
+
+    let start = Instant::now();
+    let nodes = if args.divide {
+        let counts = if args.reference {
+            divide_reference(&mut position, args.depth)
+        } else {
+            divide(&mut bits, args.depth)
+        };
+        for &(coord, nodes) in &counts {
+            println!("{}: {nodes}", Global(coord));
+        }
+        println!();
+        counts.iter().map(|&(_, nodes)| nodes).sum()
+    } else if args.reference {
+        perft_reference(&mut position, args.depth)
+    } else {
+        perft(&mut bits, args.depth)
+    };
+    let elapsed = start.elapsed();
+    println!("Nodes: {nodes}");
+    eprintln!(
+        "{:.3}s, {:.0} nodes/s",
+        elapsed.as_secs_f64(),
+        nodes as f64 / elapsed.as_secs_f64()
+    );
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,336 @@
+//! A compact copy of the rules in [`Position`] for searching lots of positions quickly.
+//!
+//! Every 3×3 board, inner or outer, is a 9-bit mask with bit `y * 3 + x` set for each taken
+//! square, so checking for a line is a single table lookup.
+
+use crate::{
+    minisquare_of, target_of, zobrist, GameOutcome, MinisquareState, Player, Position, Ruleset,
+    Termination,
+};
+
+pub(crate) const FULL: u16 = 0x1ff;
+
+/// [`position::LINES`](crate::position) as masks.
+pub(crate) const LINES: [u16; 8] = {
+    let mut masks = [0; 8];
+    let mut i = 0;
+    while i < masks.len() {
+        let mut j = 0;
+        while j < 3 {
+            masks[i] |= 1 << crate::position::LINES[i][j];
+            j += 1;
+        }
+        i += 1;
+    }
+    masks
+};
+
+/// `HAS_LINE[mask]` is whether the squares in `mask` contain three in a row.
+pub(crate) static HAS_LINE: [bool; 512] = {
+    let mut table = [false; 512];
+    let mut mask = 0;
+    while mask < 512 {
+        let mut i = 0;
+        while i < LINES.len() {
+            if mask as u16 & LINES[i] == LINES[i] {
+                table[mask] = true;
+            }
+            i += 1;
+        }
+        mask += 1;
+    }
+    table
+};
+
+/// [`coord_of`](crate::coord_of) for every square of every minisquare.
+static COORD: [[u8; 9]; 9] = {
+    let mut table = [[0; 9]; 9];
+    let mut coord = 0;
+    while coord < 81 {
+        table[minisquare_of(coord)][target_of(coord)] = coord as u8;
+        coord += 1;
+    }
+    table
+};
+
+pub(crate) fn player_index(player: Player) -> usize {
+    match player {
+        Player::Nought => 0,
+        Player::Cross => 1,
+    }
+}
+
+/// A position without history, for search. Moves are the same tile coordinates as in
+/// [`Position`], and the rules must behave identically.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitPosition {
+    rules: Ruleset,
+    /// The tiles each player has taken in each minisquare, indexed by player then minisquare.
+    tiles: [[u16; 9]; 2],
+    /// The minisquares each player has won.
+    won: [u16; 2],
+    /// The minisquares with no empty tiles left.
+    full: u16,
+    side_to_move: Player,
+    forced_board: Option<u8>,
+    outcome: GameOutcome,
+    /// Kept up to date by `make`, and always equal to [`Position::zobrist`] for the same position.
+    hash: u64,
+}
+
+impl std::hash::Hash for BitPosition {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+/// What [`BitPosition::unmake`] needs to put back.
+#[derive(Clone, Copy, Debug)]
+pub struct Unmake {
+    won: [u16; 2],
+    full: u16,
+    forced_board: Option<u8>,
+    outcome: GameOutcome,
+    hash: u64,
+}
+
+/// The legal moves in a position, in ascending order.
+#[derive(Clone, Copy, Debug)]
+pub struct MoveList {
+    moves: [u8; 81],
+    len: u8,
+}
+
+impl std::ops::Deref for MoveList {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        &self.moves[..self.len as usize]
+    }
+}
+
+impl Default for BitPosition {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BitPosition {
+    /// An empty board with cross to move, using the standard rules.
+    pub fn new() -> Self {
+        Self::with_rules(Ruleset::STANDARD)
+    }
+
+    /// An empty board with cross to move.
+    pub fn with_rules(rules: Ruleset) -> Self {
+        BitPosition {
+            rules,
+            tiles: [[0; 9]; 2],
+            won: [0; 2],
+            full: 0,
+            side_to_move: Player::Cross,
+            forced_board: None,
+            outcome: GameOutcome::Ongoing,
+            hash: 0,
+        }
+    }
+
+    pub fn tile(&self, coord: usize) -> Option<Player> {
+        let bit = 1 << target_of(coord);
+        let minisquare = minisquare_of(coord);
+        [Player::Nought, Player::Cross]
+            .into_iter()
+            .find(|&player| self.tiles[player_index(player)][minisquare] & bit != 0)
+    }
+
+    pub fn minisquare(&self, index: usize) -> MinisquareState {
+        let bit = 1 << index;
+        if self.won[player_index(Player::Nought)] & bit != 0 {
+            MinisquareState::Won(Player::Nought)
+        } else if self.won[player_index(Player::Cross)] & bit != 0 {
+            MinisquareState::Won(Player::Cross)
+        } else if self.full & bit != 0 {
+            MinisquareState::Drawn
+        } else {
+            MinisquareState::Open
+        }
+    }
+
+    pub fn rules(&self) -> Ruleset {
+        self.rules
+    }
+
+    /// The player to move, or `None` if the game has ended.
+    pub fn whose_turn(&self) -> Option<Player> {
+        (!self.outcome.is_over()).then_some(self.side_to_move)
+    }
+
+    pub fn side_to_move(&self) -> Player {
+        self.side_to_move
+    }
+
+    pub fn outcome(&self) -> GameOutcome {
+        self.outcome
+    }
+
+    /// See [`Position::zobrist`].
+    pub fn zobrist(&self) -> u64 {
+        self.hash
+    }
+
+    /// See [`Position::forced_board`].
+    pub fn forced_board(&self) -> Option<usize> {
+        self.forced_board.map(usize::from)
+    }
+
+    /// The tiles `player` has taken in `minisquare`, as a mask.
+    pub(crate) fn tiles_in(&self, player: Player, minisquare: usize) -> u16 {
+        self.tiles[player_index(player)][minisquare]
+    }
+
+    pub(crate) fn taken(&self, minisquare: usize) -> u16 {
+        self.tiles[0][minisquare] | self.tiles[1][minisquare]
+    }
+
+    /// The minisquares `player` has won, as a mask.
+    pub(crate) fn won(&self, player: Player) -> u16 {
+        self.won[player_index(player)]
+    }
+
+    /// The minisquares that are won or drawn, as a mask.
+    pub(crate) fn decided(&self) -> u16 {
+        self.won[0] | self.won[1] | self.full
+    }
+
+    /// See [`Position::is_open`]. Returns a mask.
+    pub(crate) fn open_boards(&self) -> u16 {
+        if self.rules.play_in_won_minisquares {
+            !self.full & FULL
+        } else {
+            !self.decided() & FULL
+        }
+    }
+
+    /// The minisquares the player to move may play in, as a mask.
+    fn playable_boards(&self) -> u16 {
+        if self.outcome.is_over() {
+            0
+        } else if let Some(forced) = self.forced_board {
+            1 << forced
+        } else if self.rules.free_move_anywhere {
+            !self.full & FULL
+        } else {
+            self.open_boards()
+        }
+    }
+
+    pub fn is_legal(&self, coord: usize) -> bool {
+        coord < 81
+            && self.playable_boards() & (1 << minisquare_of(coord)) != 0
+            && self.taken(minisquare_of(coord)) & (1 << target_of(coord)) == 0
+    }
+
+    pub fn legal_moves(&self) -> MoveList {
+        let mut list = MoveList {
+            moves: [0; 81],
+            len: 0,
+        };
+        let mut boards = self.playable_boards();
+        while boards != 0 {
+            let minisquare = boards.trailing_zeros() as usize;
+            boards &= boards - 1;
+            let mut empty = !self.taken(minisquare) & FULL;
+            while empty != 0 {
+                list.moves[list.len as usize] = COORD[minisquare][empty.trailing_zeros() as usize];
+                list.len += 1;
+                empty &= empty - 1;
+            }
+        }
+        // minisquares were visited in order, but their rows interleave
+        list.moves[..list.len as usize].sort_unstable();
+        list
+    }
+
+    /// Plays a legal move for the player to move, returning what's needed to take it back.
+    pub fn make(&mut self, coord: usize) -> Unmake {
+        debug_assert!(self.is_legal(coord));
+        let unmake = Unmake {
+            won: self.won,
+            full: self.full,
+            forced_board: self.forced_board,
+            outcome: self.outcome,
+            hash: self.hash,
+        };
+        let us = self.side_to_move;
+        let minisquare = minisquare_of(coord);
+        let bit = 1 << minisquare;
+        // a minisquare that's already been won stays with whoever won it first
+        let was_decided = self.decided() & bit != 0;
+        let ours = &mut self.tiles[player_index(us)][minisquare];
+        *ours |= 1 << target_of(coord);
+        self.hash ^= zobrist::tile(coord, us);
+        if !was_decided && HAS_LINE[*ours as usize] {
+            self.won[player_index(us)] |= bit;
+            self.hash ^= zobrist::minisquare(minisquare, MinisquareState::Won(us));
+        } else if !was_decided && self.taken(minisquare) == FULL {
+            self.hash ^= zobrist::minisquare(minisquare, MinisquareState::Drawn);
+        }
+        if self.taken(minisquare) == FULL {
+            self.full |= bit;
+        }
+
+        self.side_to_move = !us;
+        self.hash ^= zobrist::side_to_move(us) ^ zobrist::side_to_move(!us);
+        let target = target_of(coord);
+        self.hash ^= zobrist::forced_board(self.forced_board());
+        self.forced_board = (self.open_boards() & (1 << target) != 0).then_some(target as u8);
+        self.hash ^= zobrist::forced_board(self.forced_board());
+        if HAS_LINE[self.won[player_index(us)] as usize] {
+            self.outcome = GameOutcome::Win(us, Termination::OuterLine);
+        } else if self.playable_boards() == 0 {
+            self.outcome = self.outcome_without_line();
+        }
+        unmake
+    }
+
+    /// How the game ends when nobody can move.
+    fn outcome_without_line(&self) -> GameOutcome {
+        let won_by = |player| self.won[player_index(player)].count_ones() as usize;
+        GameOutcome::without_line(self.rules, won_by(Player::Nought), won_by(Player::Cross))
+    }
+
+    /// Takes back `coord`, which must have been the last move made.
+    pub fn unmake(&mut self, coord: usize, unmake: Unmake) {
+        self.side_to_move = !self.side_to_move;
+        self.tiles[player_index(self.side_to_move)][minisquare_of(coord)] &=
+            !(1 << target_of(coord));
+        self.won = unmake.won;
+        self.full = unmake.full;
+        self.forced_board = unmake.forced_board;
+        self.outcome = unmake.outcome;
+        self.hash = unmake.hash;
+    }
+}
+
+impl From<&Position> for BitPosition {
+    fn from(position: &Position) -> Self {
+        let mut bits = BitPosition::with_rules(position.rules());
+        for (coord, tile) in position.tiles().iter().enumerate() {
+            if let Some(player) = tile {
+                bits.tiles[player_index(*player)][minisquare_of(coord)] |= 1 << target_of(coord);
+            }
+        }
+        for (i, minisquare) in position.minisquares().iter().enumerate() {
+            if let MinisquareState::Won(player) = minisquare {
+                bits.won[player_index(*player)] |= 1 << i;
+            }
+            if bits.taken(i) == FULL {
+                bits.full |= 1 << i;
+            }
+        }
+        bits.side_to_move = position.side_to_move();
+        bits.forced_board = position.forced_board().map(|board| board as u8);
+        bits.outcome = position.outcome();
+        bits.hash = position.zobrist();
+        bits
+    }
+}
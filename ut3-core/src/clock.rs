@@ -0,0 +1,174 @@
+//! Chess-style clocks. Time is passed in rather than read, so the same clock can be driven by a
+//! real timer, a test or a network peer.
+
+use std::{
+    fmt,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use crate::{bitboard::player_index, Player};
+
+/// How much time each player gets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TimeControl {
+    /// `base` for the whole game, plus `increment` after every move. Written like `300+5`, in
+    /// seconds.
+    Increment { base: Duration, increment: Duration },
+    /// The same time for every move, with nothing carried over. Written like `30/move`.
+    PerMove(Duration),
+}
+
+impl TimeControl {
+    /// How much time each player starts with.
+    pub fn initial(self) -> Duration {
+        match self {
+            TimeControl::Increment { base, .. } => base,
+            TimeControl::PerMove(limit) => limit,
+        }
+    }
+}
+
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeControl::Increment { base, increment } => {
+                write!(f, "{}+{}", base.as_secs(), increment.as_secs())
+            }
+            TimeControl::PerMove(limit) => write!(f, "{}/move", limit.as_secs()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BadTimeControl(pub String);
+
+impl fmt::Display for BadTimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "bad time control {:?}, expected something like 300+5 or 30/move",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for BadTimeControl {}
+
+impl FromStr for TimeControl {
+    type Err = BadTimeControl;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bad = || BadTimeControl(s.to_owned());
+        let seconds = |n: &str| n.trim().parse().map(Duration::from_secs).map_err(|_| bad());
+        let control = if let Some(limit) = s.strip_suffix("/move") {
+            TimeControl::PerMove(seconds(limit)?)
+        } else if let Some((base, increment)) = s.split_once('+') {
+            TimeControl::Increment {
+                base: seconds(base)?,
+                increment: seconds(increment)?,
+            }
+        } else {
+            return Err(bad());
+        };
+        if control.initial().is_zero() {
+            return Err(bad());
+        }
+        Ok(control)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Clock {
+    control: TimeControl,
+    /// Each player's time as of the start of their turn, or now if it isn't their turn.
+    remaining: [Duration; 2],
+    /// Whose clock is running, and since when.
+    running: Option<(Player, Instant)>,
+}
+
+impl Clock {
+    /// A clock with both players' full time, and neither running.
+    pub fn new(control: TimeControl) -> Self {
+        Clock {
+            control,
+            remaining: [control.initial(); 2],
+            running: None,
+        }
+    }
+
+    pub fn control(&self) -> TimeControl {
+        self.control
+    }
+
+    /// Whose clock is running, if any.
+    pub fn running(&self) -> Option<Player> {
+        self.running.map(|(player, _)| player)
+    }
+
+    /// Starts `player`'s clock, stopping the other one.
+    pub fn start(&mut self, player: Player, now: Instant) {
+        self.stop(now);
+        self.running = Some((player, now));
+    }
+
+    /// Stops whichever clock is running.
+    pub fn stop(&mut self, now: Instant) {
+        if let Some((player, _)) = self.running {
+            self.remaining[player_index(player)] = self.remaining(player, now);
+            self.running = None;
+        }
+    }
+
+    /// How long `player` has left, which is zero once they've run out.
+    pub fn remaining(&self, player: Player, now: Instant) -> Duration {
+        let remaining = self.remaining[player_index(player)];
+        match self.running {
+            Some((running, since)) if running == player => {
+                remaining.saturating_sub(now.saturating_duration_since(since))
+            }
+            _ => remaining,
+        }
+    }
+
+    /// Sets how long `player` has left, e.g. when the opponent reports their time after a move.
+    pub fn set_remaining(&mut self, player: Player, remaining: Duration, now: Instant) {
+        self.remaining[player_index(player)] = remaining;
+        if let Some((running, since)) = &mut self.running {
+            if *running == player {
+                *since = now;
+            }
+        }
+    }
+
+    /// The player whose time has run out, if any.
+    pub fn flagged(&self, now: Instant) -> Option<Player> {
+        let player = self.running()?;
+        self.remaining(player, now).is_zero().then_some(player)
+    }
+
+    /// The player whose time ran out at least `grace` ago, if any. For a clock that runs ahead of
+    /// the player's own, such as ours of a network opponent, whose moves take a while to arrive.
+    pub fn flagged_by(&self, grace: Duration, now: Instant) -> Option<Player> {
+        let (player, since) = self.running?;
+        let used = now.saturating_duration_since(since);
+        (used >= self.remaining[player_index(player)] + grace).then_some(player)
+    }
+
+    /// Ends the running player's turn and starts the other's, applying the increment. Returns
+    /// how long the player who moved has left.
+    pub fn press(&mut self, now: Instant) -> Duration {
+        let Some((player, _)) = self.running else {
+            return Duration::ZERO;
+        };
+        self.stop(now);
+        let remaining = &mut self.remaining[player_index(player)];
+        match self.control {
+            TimeControl::Increment { increment, .. } => *remaining += increment,
+            TimeControl::PerMove(limit) => *remaining = limit,
+        }
+        let remaining = *remaining;
+        self.start(!player, now);
+        remaining
+    }
+}
@@ -0,0 +1,455 @@
+//! A line-based protocol for talking to engines over stdin and stdout, modelled on chess's UCI, so
+//! that bots written in any language can play in the GUI.
+//!
+//! The GUI sends commands, one per line:
+//!
+//! - `ut3` starts the conversation. The engine replies with any number of `id name ...` and
+//!   `id author ...` lines, then `ut3ok`.
+//! - `isready` asks the engine to reply `readyok` once it has finished with earlier commands.
+//! - `newgame` says the next position is from a different game, in case the engine keeps anything
+//!   between moves.
+//! - `position startpos [rules RULES] [moves MOVE...]` or `position fen FEN [rules RULES] [moves
+//!   MOVE...]` sets the position to search, as the start of the game or a
+//!   [position string](crate::fen), then the moves played since. Rules are written like
+//!   `play-in-won,minisquare-tiebreak`, and are standard if left out. Moves are in
+//!   [global notation](crate::notation::Global).
+//! - `go [movetime MS] [depth N] [nodes N]` searches the position. Whichever limit is reached
+//!   first ends the search, and with none the engine thinks until it's sure of the result.
+//! - `quit`.
+//!
+//! While searching, the engine can send `info depth N score SCORE nodes N time MS pv MOVE...`
+//! lines, where the score is from the point of view of the player to move and is a number, or
+//! `win N` or `loss N` for a forced result `N` moves away. Every field is optional. Then it sends
+//! `bestmove MOVE`, or `bestmove none` if the game is over or the last `position` command
+//! couldn't be used.
+//!
+//! ```text
+//! > ut3
+//! < id name ut3-engine
+//! < ut3ok
+//! > position startpos moves E5 E4
+//! > go movetime 1000
+//! < info depth 7 score 40 nodes 282137 time 120 pv D2 A4 B3
+//! < bestmove D2
+//! ```
+//!
+//! Either side ignores lines it doesn't understand, so the protocol can grow.
+
+use std::{fmt, str::FromStr, time::Duration};
+
+use crate::{
+    fen::ParsePositionError,
+    notation::{self, Global, ParseMoveError},
+    record::GameRecord,
+    search::{self, Limits, SearchResult, WIN},
+    BitPosition, IllegalMove, Position, Ruleset,
+};
+
+/// From the GUI to the engine.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    Ut3,
+    IsReady,
+    NewGame,
+    Position {
+        /// A position string, or `None` for the start of the game.
+        start: Option<String>,
+        rules: Ruleset,
+        moves: Vec<usize>,
+    },
+    Go(Limits),
+    Quit,
+}
+
+/// From the engine to the GUI.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Response {
+    Id {
+        name: String,
+        value: String,
+    },
+    Ut3Ok,
+    ReadyOk,
+    Info(Info),
+    /// `None` if the game is over.
+    BestMove(Option<usize>),
+}
+
+/// What the engine is thinking. Fields that weren't sent are `None` or empty.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Info {
+    pub depth: Option<u32>,
+    /// As in [`search`](crate::search).
+    pub score: Option<i32>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,
+    pub pv: Vec<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProtocolError {
+    /// The line doesn't start with anything known.
+    Unknown(String),
+    /// `name` is missing its value, or it doesn't parse.
+    BadValue {
+        name: &'static str,
+        value: String,
+    },
+    BadPosition(ParsePositionError),
+    BadMove(ParseMoveError),
+    /// `ply` counts from 1 for the first move after the starting position.
+    IllegalMove {
+        ply: usize,
+        error: IllegalMove,
+    },
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocolError::Unknown(line) => write!(f, "unknown command {line:?}"),
+            ProtocolError::BadValue { name, value } => write!(f, "bad {name} {value:?}"),
+            ProtocolError::BadPosition(e) => write!(f, "bad position: {e}"),
+            ProtocolError::BadMove(e) => e.fmt(f),
+            ProtocolError::IllegalMove { ply, error } => {
+                write!(f, "move {ply} is illegal: {error}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+impl Command {
+    /// A `position` command for the game that led to `position`, from its start.
+    pub fn position(position: &Position) -> Self {
+        let record = GameRecord::from_position(position);
+        Command::Position {
+            start: record.start,
+            rules: record.rules,
+            moves: record.moves,
+        }
+    }
+}
+
+/// Plays `moves` from `start`, as in a `position` command.
+pub fn replay(
+    start: Option<&str>,
+    rules: Ruleset,
+    moves: &[usize],
+) -> Result<Position, ProtocolError> {
+    let mut position = match start {
+        Some(fen) => Position::from_fen(fen, rules).map_err(ProtocolError::BadPosition)?,
+        None => Position::with_rules(rules),
+    };
+    for (i, &coord) in moves.iter().enumerate() {
+        position
+            .try_play(coord)
+            .map_err(|error| ProtocolError::IllegalMove { ply: i + 1, error })?;
+    }
+    Ok(position)
+}
+
+fn write_moves(f: &mut fmt::Formatter<'_>, moves: &[usize]) -> fmt::Result {
+    moves
+        .iter()
+        .try_for_each(|&coord| write!(f, " {}", Global(coord)))
+}
+
+fn parse_value<T: FromStr>(name: &'static str, value: Option<&str>) -> Result<T, ProtocolError> {
+    let bad = || ProtocolError::BadValue {
+        name,
+        value: value.unwrap_or_default().to_owned(),
+    };
+    value.ok_or_else(bad)?.parse().map_err(|_| bad())
+}
+
+fn parse_moves<'a>(tokens: impl Iterator<Item = &'a str>) -> Result<Vec<usize>, ProtocolError> {
+    tokens
+        .map(|token| notation::parse_move(token).map_err(ProtocolError::BadMove))
+        .collect()
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::Ut3 => f.write_str("ut3"),
+            Command::IsReady => f.write_str("isready"),
+            Command::NewGame => f.write_str("newgame"),
+            Command::Position {
+                start,
+                rules,
+                moves,
+            } => {
+                match start {
+                    Some(fen) => write!(f, "position fen {fen}")?,
+                    None => f.write_str("position startpos")?,
+                }
+                if *rules != Ruleset::STANDARD {
+                    write!(f, " rules {rules}")?;
+                }
+                if !moves.is_empty() {
+                    f.write_str(" moves")?;
+                    write_moves(f, moves)?;
+                }
+                Ok(())
+            }
+            Command::Go(limits) => {
+                f.write_str("go")?;
+                if let Some(time) = limits.time {
+                    write!(f, " movetime {}", time.as_millis())?;
+                }
+                if let Some(depth) = limits.depth {
+                    write!(f, " depth {depth}")?;
+                }
+                if let Some(nodes) = limits.nodes {
+                    write!(f, " nodes {nodes}")?;
+                }
+                Ok(())
+            }
+            Command::Quit => f.write_str("quit"),
+        }
+    }
+}
+
+impl FromStr for Command {
+    type Err = ProtocolError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = s.split_whitespace().peekable();
+        let command = match tokens.next().unwrap_or_default() {
+            "ut3" => Command::Ut3,
+            "isready" => Command::IsReady,
+            "newgame" => Command::NewGame,
+            "position" => {
+                let start = match tokens.next() {
+                    Some("startpos") => None,
+                    Some("fen") => {
+                        // a position string has spaces in
+                        let fields: Vec<_> = std::iter::from_fn(|| {
+                            tokens.next_if(|&token| token != "rules" && token != "moves")
+                        })
+                        .collect();
+                        Some(fields.join(" "))
+                    }
+                    other => {
+                        return Err(ProtocolError::BadValue {
+                            name: "position",
+                            value: other.unwrap_or_default().to_owned(),
+                        })
+                    }
+                };
+                let rules = match tokens.next_if_eq(&"rules") {
+                    Some(_) => parse_value("rules", tokens.next())?,
+                    None => Ruleset::STANDARD,
+                };
+                let moves = match tokens.next() {
+                    Some("moves") => parse_moves(tokens.by_ref())?,
+                    None => Vec::new(),
+                    Some(other) => return Err(ProtocolError::Unknown(other.to_owned())),
+                };
+                Command::Position {
+                    start,
+                    rules,
+                    moves,
+                }
+            }
+            "go" => {
+                let mut limits = Limits::default();
+                while let Some(name) = tokens.next() {
+                    match name {
+                        "movetime" => {
+                            let ms = parse_value("movetime", tokens.next())?;
+                            limits.time = Some(Duration::from_millis(ms));
+                        }
+                        "depth" => limits.depth = Some(parse_value("depth", tokens.next())?),
+                        "nodes" => limits.nodes = Some(parse_value("nodes", tokens.next())?),
+                        _ => {}
+                    }
+                }
+                Command::Go(limits)
+            }
+            "quit" => Command::Quit,
+            _ => return Err(ProtocolError::Unknown(s.to_owned())),
+        };
+        Ok(command)
+    }
+}
+
+impl fmt::Display for Response {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Response::Id { name, value } => write!(f, "id {name} {value}"),
+            Response::Ut3Ok => f.write_str("ut3ok"),
+            Response::ReadyOk => f.write_str("readyok"),
+            Response::Info(info) => {
+                f.write_str("info")?;
+                if let Some(depth) = info.depth {
+                    write!(f, " depth {depth}")?;
+                }
+                match info.score {
+                    Some(score) if search::is_decisive(score) && score > 0 => {
+                        write!(f, " score win {}", WIN - score)?
+                    }
+                    Some(score) if search::is_decisive(score) => {
+                        write!(f, " score loss {}", WIN + score)?
+                    }
+                    Some(score) => write!(f, " score {score}")?,
+                    None => {}
+                }
+                if let Some(nodes) = info.nodes {
+                    write!(f, " nodes {nodes}")?;
+                }
+                if let Some(time) = info.time {
+                    write!(f, " time {}", time.as_millis())?;
+                }
+                if !info.pv.is_empty() {
+                    f.write_str(" pv")?;
+                    write_moves(f, &info.pv)?;
+                }
+                Ok(())
+            }
+            Response::BestMove(Some(coord)) => write!(f, "bestmove {}", Global(*coord)),
+            Response::BestMove(None) => f.write_str("bestmove none"),
+        }
+    }
+}
+
+impl FromStr for Response {
+    type Err = ProtocolError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = s.split_whitespace();
+        let response = match tokens.next().unwrap_or_default() {
+            "id" => Response::Id {
+                name: parse_value("id", tokens.next())?,
+                value: tokens.collect::<Vec<_>>().join(" "),
+            },
+            "ut3ok" => Response::Ut3Ok,
+            "readyok" => Response::ReadyOk,
+            "info" => {
+                let mut info = Info::default();
+                while let Some(name) = tokens.next() {
+                    match name {
+                        "depth" => info.depth = Some(parse_value("depth", tokens.next())?),
+                        "score" => {
+                            info.score = Some(match tokens.next() {
+                                Some("win") => WIN - parse_value::<i32>("score", tokens.next())?,
+                                Some("loss") => parse_value::<i32>("score", tokens.next())? - WIN,
+                                score => parse_value("score", score)?,
+                            })
+                        }
+                        "nodes" => info.nodes = Some(parse_value("nodes", tokens.next())?),
+                        "time" => {
+                            let ms = parse_value("time", tokens.next())?;
+                            info.time = Some(Duration::from_millis(ms));
+                        }
+                        // the pv is always last
+                        "pv" => info.pv = parse_moves(tokens.by_ref())?,
+                        _ => {}
+                    }
+                }
+                Response::Info(info)
+            }
+            "bestmove" => match tokens.next() {
+                Some("none") => Response::BestMove(None),
+                coord => Response::BestMove(Some(
+                    notation::parse_move(coord.unwrap_or_default())
+                        .map_err(ProtocolError::BadMove)?,
+                )),
+            },
+            _ => return Err(ProtocolError::Unknown(s.to_owned())),
+        };
+        Ok(response)
+    }
+}
+
+impl From<&SearchResult> for Info {
+    fn from(result: &SearchResult) -> Self {
+        Info {
+            depth: Some(result.depth),
+            score: Some(result.score),
+            nodes: Some(result.nodes),
+            time: None,
+            pv: result.pv.clone(),
+        }
+    }
+}
+
+/// The engine side of the protocol, answering with the built-in [`search`].
+pub struct Engine {
+    name: String,
+    /// `None` after a `position` command that couldn't be used, until the next good one.
+    position: Option<Position>,
+}
+
+impl Engine {
+    pub fn new(name: impl Into<String>) -> Self {
+        Engine {
+            name: name.into(),
+            position: Some(Position::new()),
+        }
+    }
+
+    /// The position that `go` will search, if the last `position` command could be used.
+    pub fn position(&self) -> Option<&Position> {
+        self.position.as_ref()
+    }
+
+    /// Parses and handles a line from the GUI. A `position` line that doesn't parse still drops
+    /// the old position, so that the next `go` doesn't search a position from another game.
+    pub fn handle_line(&mut self, line: &str) -> Result<Vec<Response>, ProtocolError> {
+        match line.parse() {
+            Ok(command) => self.handle(&command),
+            Err(e) => {
+                if line.split_whitespace().next() == Some("position") {
+                    self.position = None;
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// What to send back for `command`. Searching happens right here, so this blocks until the
+    /// search is done.
+    pub fn handle(&mut self, command: &Command) -> Result<Vec<Response>, ProtocolError> {
+        let responses = match command {
+            Command::Ut3 => vec![
+                Response::Id {
+                    name: "name".to_owned(),
+                    value: self.name.clone(),
+                },
+                Response::Ut3Ok,
+            ],
+            Command::IsReady => vec![Response::ReadyOk],
+            Command::NewGame => {
+                self.position = Some(Position::new());
+                Vec::new()
+            }
+            Command::Position {
+                start,
+                rules,
+                moves,
+            } => {
+                // stays cleared if this one is no good
+                self.position = None;
+                self.position = Some(replay(start.as_deref(), *rules, moves)?);
+                Vec::new()
+            }
+            Command::Go(limits) => {
+                let Some(position) = &self.position else {
+                    return Ok(vec![Response::BestMove(None)]);
+                };
+                let start = std::time::Instant::now();
+                let result = search::search(&BitPosition::from(position), *limits);
+                let info = Info {
+                    time: Some(start.elapsed()),
+                    ..Info::from(&result)
+                };
+                vec![Response::Info(info), Response::BestMove(result.best_move)]
+            }
+            Command::Quit => Vec::new(),
+        };
+        Ok(responses)
+    }
+}
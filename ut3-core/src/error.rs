@@ -0,0 +1,33 @@
+use std::fmt;
+
+/// Why a move was rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IllegalMove {
+    /// The coordinate isn't one of the 81 tiles.
+    OutOfRange,
+    /// Someone has already played on that tile.
+    Occupied,
+    /// The last move sent the player to a different minisquare.
+    WrongBoard,
+    /// The tile's minisquare has been won or drawn, and the rules don't allow playing there.
+    ClosedBoard,
+    /// The move was made on behalf of the player who isn't to move.
+    NotYourTurn,
+    /// The game has already finished.
+    GameOver,
+}
+
+impl fmt::Display for IllegalMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            IllegalMove::OutOfRange => "there is no such tile",
+            IllegalMove::Occupied => "that tile is already taken",
+            IllegalMove::WrongBoard => "the last move sent play to a different minisquare",
+            IllegalMove::ClosedBoard => "that minisquare is closed",
+            IllegalMove::NotYourTurn => "it is not that player's turn",
+            IllegalMove::GameOver => "the game has already ended",
+        })
+    }
+}
+
+impl std::error::Error for IllegalMove {}
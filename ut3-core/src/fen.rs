@@ -0,0 +1,236 @@
+//! A one-line text form of a position, in the style of chess's FEN, for pasting into bug reports
+//! and tests. It has four fields separated by spaces:
+//!
+//! 1. The tiles, as nine rows from the top separated by `/`. Each row has `x` and `o` for taken
+//!    tiles, and a single digit for each run of empty ones, like `x2o5`.
+//! 2. The nine minisquares in the same order: `x` or `o` if won, `=` if drawn and `.` if open.
+//! 3. Whose turn it is, `x` or `o`.
+//! 4. The minisquare the player to move was sent to (see [`notation::Minisquare`]), or `-` if
+//!    they can play anywhere.
+//!
+//! So after cross plays in the very centre, the position is
+//! `9/9/9/9/4x4/9/9/9/9 ......... o C`.
+
+use std::{fmt, str::FromStr};
+
+use crate::{coord_of, notation, position::has_line, MinisquareState, Player, Position, Ruleset};
+
+/// The position at the start of a game.
+pub const STARTPOS: &str = "9/9/9/9/9/9/9/9/9 ......... x -";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParsePositionError {
+    /// There should be four fields separated by spaces.
+    WrongFieldCount(usize),
+    BadTiles(String),
+    BadMinisquares(String),
+    BadSideToMove(String),
+    BadForcedBoard(String),
+    /// Every field parsed, but together they describe a position that can't come up in a game.
+    Inconsistent(&'static str),
+}
+
+impl fmt::Display for ParsePositionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParsePositionError::WrongFieldCount(n) => {
+                write!(f, "expected 4 fields separated by spaces, found {n}")
+            }
+            ParsePositionError::BadTiles(s) => write!(f, "bad tiles {s:?}"),
+            ParsePositionError::BadMinisquares(s) => write!(f, "bad minisquares {s:?}"),
+            ParsePositionError::BadSideToMove(s) => write!(f, "bad side to move {s:?}"),
+            ParsePositionError::BadForcedBoard(s) => write!(f, "bad forced minisquare {s:?}"),
+            ParsePositionError::Inconsistent(why) => f.write_str(why),
+        }
+    }
+}
+
+impl std::error::Error for ParsePositionError {}
+
+fn player_char(player: Player) -> char {
+    match player {
+        Player::Nought => 'o',
+        Player::Cross => 'x',
+    }
+}
+
+fn parse_player(c: char) -> Option<Player> {
+    match c {
+        'o' => Some(Player::Nought),
+        'x' => Some(Player::Cross),
+        _ => None,
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (y, row) in self.tiles().chunks(9).enumerate() {
+            if y > 0 {
+                f.write_str("/")?;
+            }
+            let mut empty = 0;
+            for tile in row {
+                match tile {
+                    Some(player) => {
+                        if empty > 0 {
+                            write!(f, "{empty}")?;
+                            empty = 0;
+                        }
+                        write!(f, "{}", player_char(*player))?;
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                write!(f, "{empty}")?;
+            }
+        }
+        f.write_str(" ")?;
+        for minisquare in self.minisquares() {
+            let c = match minisquare {
+                MinisquareState::Open => '.',
+                MinisquareState::Won(player) => player_char(*player),
+                MinisquareState::Drawn => '=',
+            };
+            write!(f, "{c}")?;
+        }
+        write!(f, " {} ", player_char(self.side_to_move()))?;
+        match self.forced_board() {
+            Some(board) => write!(f, "{}", notation::Minisquare(board)),
+            None => f.write_str("-"),
+        }
+    }
+}
+
+fn parse_tiles(s: &str) -> Option<[Option<Player>; 81]> {
+    let mut tiles = [None; 81];
+    let rows: Vec<&str> = s.split('/').collect();
+    if rows.len() != 9 {
+        return None;
+    }
+    for (y, row) in rows.into_iter().enumerate() {
+        let mut x = 0;
+        let mut after_digit = false;
+        for c in row.chars() {
+            if let Some(empty) = c.to_digit(10) {
+                // a run of empty tiles is always written as one digit, so each position has
+                // exactly one string
+                if after_digit || empty == 0 {
+                    return None;
+                }
+                x += empty as usize;
+                after_digit = true;
+            } else {
+                *tiles.get_mut(y * 9 + x).filter(|_| x < 9)? = Some(parse_player(c)?);
+                x += 1;
+                after_digit = false;
+            }
+        }
+        if x != 9 {
+            return None;
+        }
+    }
+    Some(tiles)
+}
+
+fn parse_minisquares(s: &str) -> Option<[MinisquareState; 9]> {
+    let mut minisquares = [MinisquareState::Open; 9];
+    let mut chars = s.chars();
+    for minisquare in &mut minisquares {
+        *minisquare = match chars.next()? {
+            '.' => MinisquareState::Open,
+            '=' => MinisquareState::Drawn,
+            c => MinisquareState::Won(parse_player(c)?),
+        };
+    }
+    chars.next().is_none().then_some(minisquares)
+}
+
+/// Checks that each minisquare's state matches the tiles in it.
+fn check_minisquares(
+    rules: Ruleset,
+    tiles: &[Option<Player>; 81],
+    minisquares: &[MinisquareState; 9],
+) -> Result<(), ParsePositionError> {
+    for (minisquare, state) in minisquares.iter().enumerate() {
+        let tile = |cell| tiles[coord_of(minisquare, cell)];
+        let has_line_for = |player| has_line(|cell| tile(cell) == Some(player));
+        let crosses = has_line_for(Player::Cross);
+        let noughts = has_line_for(Player::Nought);
+        let is_full = (0..9).all(|cell| tile(cell).is_some());
+        let consistent = match state {
+            MinisquareState::Open => !crosses && !noughts && !is_full,
+            MinisquareState::Drawn => !crosses && !noughts && is_full,
+            // the loser can only have caught up if play carried on after it was won
+            MinisquareState::Won(Player::Cross) => {
+                crosses && (!noughts || rules.play_in_won_minisquares)
+            }
+            MinisquareState::Won(Player::Nought) => {
+                noughts && (!crosses || rules.play_in_won_minisquares)
+            }
+        };
+        if !consistent {
+            return Err(ParsePositionError::Inconsistent(
+                "a minisquare's state doesn't match the tiles in it",
+            ));
+        }
+    }
+    let lines = [Player::Cross, Player::Nought]
+        .map(|player| has_line(|i| minisquares[i] == MinisquareState::Won(player)));
+    if lines == [true, true] {
+        return Err(ParsePositionError::Inconsistent(
+            "both players have a line of minisquares",
+        ));
+    }
+    Ok(())
+}
+
+impl Position {
+    /// Parses a position written by [`Position`'s `Display` impl](#impl-Display-for-Position),
+    /// to be played with `rules`. The `FromStr` impl uses [`Ruleset::STANDARD`].
+    pub fn from_fen(s: &str, rules: Ruleset) -> Result<Position, ParsePositionError> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        let [tiles, minisquares, side_to_move, forced_board] = fields[..] else {
+            return Err(ParsePositionError::WrongFieldCount(fields.len()));
+        };
+        let tiles =
+            parse_tiles(tiles).ok_or_else(|| ParsePositionError::BadTiles(tiles.to_owned()))?;
+        let minisquares = parse_minisquares(minisquares)
+            .ok_or_else(|| ParsePositionError::BadMinisquares(minisquares.to_owned()))?;
+        let side_to_move = match side_to_move {
+            "x" => Player::Cross,
+            "o" => Player::Nought,
+            _ => return Err(ParsePositionError::BadSideToMove(side_to_move.to_owned())),
+        };
+        let forced_board = match forced_board {
+            "-" => None,
+            board => Some(
+                notation::parse_minisquare(board)
+                    .map_err(|_| ParsePositionError::BadForcedBoard(board.to_owned()))?,
+            ),
+        };
+
+        let count = |player| tiles.iter().filter(|&&tile| tile == Some(player)).count();
+        let crosses_ahead = count(Player::Cross) as isize - count(Player::Nought) as isize;
+        if crosses_ahead != (side_to_move == Player::Nought) as isize {
+            return Err(ParsePositionError::Inconsistent(
+                "the numbers of crosses and noughts don't match whose turn it is",
+            ));
+        }
+        check_minisquares(rules, &tiles, &minisquares)?;
+        let position = Position::from_parts(rules, tiles, minisquares, side_to_move, forced_board);
+        if forced_board.is_some_and(|board| !position.is_open(board)) {
+            return Err(ParsePositionError::Inconsistent(
+                "the player to move was sent to a closed minisquare",
+            ));
+        }
+        Ok(position)
+    }
+}
+
+impl FromStr for Position {
+    type Err = ParsePositionError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Position::from_fen(s, Ruleset::STANDARD)
+    }
+}
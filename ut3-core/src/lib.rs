@@ -0,0 +1,52 @@
+//! The rules of ultimate tic-tac-toe, without any of the GUI or networking.
+//!
+//! Tiles are numbered 0..81 left to right, top to bottom across the whole board, and minisquares
+//! are numbered 0..9 in the same order.
+
+mod bitboard;
+pub mod clock;
+pub mod engine;
+mod error;
+pub mod fen;
+pub mod mcts;
+pub mod nested;
+pub mod notation;
+mod outcome;
+pub mod perft;
+mod position;
+pub mod record;
+mod ruleset;
+pub mod search;
+pub mod symmetry;
+mod zobrist;
+
+pub use bitboard::{BitPosition, MoveList, Unmake};
+pub use error::IllegalMove;
+pub use outcome::{GameOutcome, Termination, UnknownTermination};
+pub use position::{coord_of, minisquare_of, target_of, MinisquareState, MoveEffects, Position};
+pub use ruleset::{Ruleset, UnknownRule};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Player {
+    Nought,
+    Cross,
+}
+
+impl std::ops::Not for Player {
+    type Output = Self;
+    fn not(self) -> Self::Output {
+        match self {
+            Player::Nought => Player::Cross,
+            Player::Cross => Player::Nought,
+        }
+    }
+}
+
+impl std::fmt::Display for Player {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Player::Nought => "Nought",
+            Player::Cross => "Cross",
+        })
+    }
+}
@@ -0,0 +1,186 @@
+//! A computer player using Monte Carlo tree search: play lots of random games, and spend more of
+//! them on the moves that have been doing well.
+
+use std::time::{Duration, Instant};
+
+use crate::{BitPosition, GameOutcome};
+
+/// How long to think for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Budget {
+    /// A fixed number of random games, so the same seed always gives the same move.
+    Playouts(u32),
+    Time(Duration),
+}
+
+/// How much to favour moves that haven't been tried much over ones that have been doing well.
+const EXPLORATION: f32 = 1.4;
+
+/// Beyond this the tree stops growing, and new playouts start from its leaves.
+const MAX_NODES: usize = 1 << 22;
+
+struct Node {
+    /// The move that led here.
+    coord: u8,
+    /// `nodes[first_child..first_child + child_count]`, once the node has been expanded.
+    first_child: u32,
+    child_count: u8,
+    expanded: bool,
+    visits: u32,
+    /// The total result of the games through here, for whoever made `coord`: 1 for a win, a half
+    /// for a draw.
+    score: f32,
+}
+
+impl Node {
+    fn new(coord: u8) -> Self {
+        Node {
+            coord,
+            first_child: 0,
+            child_count: 0,
+            expanded: false,
+            visits: 0,
+            score: 0.,
+        }
+    }
+
+    fn children(&self) -> std::ops::Range<usize> {
+        let first = self.first_child as usize;
+        first..first + self.child_count as usize
+    }
+}
+
+/// splitmix64, which is fine with any seed.
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        (z % n as u64) as usize
+    }
+}
+
+struct Tree {
+    root: BitPosition,
+    nodes: Vec<Node>,
+    rng: Rng,
+    /// Kept between playouts to save allocating.
+    path: Vec<usize>,
+}
+
+impl Tree {
+    fn new(root: BitPosition, seed: u64) -> Self {
+        let mut tree = Tree {
+            root,
+            nodes: vec![Node::new(0)],
+            rng: Rng(seed),
+            path: Vec::new(),
+        };
+        tree.expand(0, &root);
+        tree
+    }
+
+    fn expand(&mut self, node: usize, position: &BitPosition) {
+        let moves = position.legal_moves();
+        let first_child = self.nodes.len();
+        self.nodes
+            .extend(moves.iter().map(|&coord| Node::new(coord)));
+        let node = &mut self.nodes[node];
+        node.first_child = first_child as u32;
+        node.child_count = moves.len() as u8;
+        node.expanded = true;
+    }
+
+    /// The child to look at next, trying each once before favouring the best. `None` if the game
+    /// is over.
+    fn select(&self, node: usize) -> Option<usize> {
+        let parent = &self.nodes[node];
+        let log_visits = (parent.visits.max(1) as f32).ln();
+        let uct = |child: &Node| {
+            if child.visits == 0 {
+                return f32::INFINITY;
+            }
+            let visits = child.visits as f32;
+            child.score / visits + EXPLORATION * (log_visits / visits).sqrt()
+        };
+        parent
+            .children()
+            .max_by(|&a, &b| uct(&self.nodes[a]).total_cmp(&uct(&self.nodes[b])))
+    }
+
+    /// Plays random moves until the game ends.
+    fn rollout(&mut self, mut position: BitPosition) -> GameOutcome {
+        while !position.outcome().is_over() {
+            let moves = position.legal_moves();
+            position.make(moves[self.rng.below(moves.len())] as usize);
+        }
+        position.outcome()
+    }
+
+    fn playout(&mut self) {
+        let mut position = self.root;
+        let mut node = 0;
+        self.path.clear();
+        self.path.push(0);
+        loop {
+            if !self.nodes[node].expanded {
+                // leaves are only expanded once a game has been played through them
+                if self.nodes[node].visits == 0 || self.nodes.len() >= MAX_NODES {
+                    break;
+                }
+                self.expand(node, &position);
+            }
+            let Some(child) = self.select(node) else {
+                break;
+            };
+            position.make(self.nodes[child].coord as usize);
+            self.path.push(child);
+            node = child;
+        }
+
+        let outcome = self.rollout(position);
+        let us = self.root.side_to_move();
+        for (depth, &node) in self.path.iter().enumerate() {
+            // the root's move was made by nobody, and then the players take turns
+            let mover = if depth.is_multiple_of(2) { !us } else { us };
+            let node = &mut self.nodes[node];
+            node.visits += 1;
+            node.score += match outcome {
+                GameOutcome::Win(winner, _) if winner == mover => 1.,
+                GameOutcome::Win(..) => 0.,
+                _ => 0.5,
+            };
+        }
+    }
+
+    /// The move that was looked at most, which is steadier than the one with the best score.
+    fn best_move(&self) -> Option<usize> {
+        let children = self.nodes[0].children();
+        // reversed so that ties go to the first move
+        children
+            .rev()
+            .max_by_key(|&child| self.nodes[child].visits)
+            .map(|child| self.nodes[child].coord as usize)
+    }
+}
+
+/// The move the search likes best for the player to move, or `None` if the game is over. The
+/// seed picks the random games, so the computer doesn't always play the same way.
+pub fn best_move(position: &BitPosition, budget: Budget, seed: u64) -> Option<usize> {
+    position.whose_turn()?;
+    let mut tree = Tree::new(*position, seed);
+    let start = Instant::now();
+    let mut playouts = 0;
+    while match budget {
+        Budget::Playouts(limit) => playouts < limit,
+        Budget::Time(limit) => start.elapsed() < limit,
+    } {
+        tree.playout();
+        playouts += 1;
+    }
+    tree.best_move()
+}
@@ -0,0 +1,440 @@
+//! Ultimate tic-tac-toe on other shapes of board: N×N grids of N×N boards, more levels of boards
+//! inside boards, and lines shorter than the board is wide.
+//!
+//! A tile's *path* is the cell it's in on the outer board, then the cell within that board, and
+//! so on down to the tile itself. Playing a tile sends the opponent to the board at the same path
+//! with the first step dropped, which on the standard board is the usual rule. With three levels,
+//! playing in the north-west tile of the centre small board of the east medium board sends the
+//! opponent to the north-west small board of the centre medium board. If that board is closed,
+//! they can play anywhere in the closest enclosing board that's still open.
+//!
+//! This is a separate model of the game rather than a generalisation of
+//! [`Position`](crate::Position), which only handles the standard board. Nothing else in the crate
+//! (position strings, notation, game records, clocks, [`BitPosition`](crate::BitPosition) and
+//! the search) knows about other boards, so they can only be played hot-seat. [`NestedPosition`]
+//! plays exactly the same games as `Position` on [`Geometry::STANDARD`], and shares how games
+//! end with it.
+
+use std::fmt;
+
+use crate::{GameOutcome, IllegalMove, MinisquareState, Player, Ruleset, Termination};
+
+/// The shape of the board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Geometry {
+    /// How many cells along each edge of every board.
+    pub size: usize,
+    /// How many levels of boards there are: 1 is plain tic-tac-toe, 2 is ultimate tic-tac-toe and
+    /// 3 has boards of boards of boards.
+    pub depth: usize,
+    /// How many cells in a row win a board.
+    pub win_length: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BadGeometry {
+    Size(usize),
+    Depth(usize),
+    WinLength(usize),
+    /// The whole board would be this many tiles across, more than [`Geometry::MAX_SIDE`].
+    Side(usize),
+}
+
+impl fmt::Display for BadGeometry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BadGeometry::Size(size) => write!(
+                f,
+                "boards must be between 2 and {} cells wide, not {size}",
+                Geometry::MAX_SIZE
+            ),
+            BadGeometry::Depth(depth) => write!(
+                f,
+                "there must be between 1 and {} levels of boards, not {depth}",
+                Geometry::MAX_DEPTH
+            ),
+            BadGeometry::WinLength(length) => write!(
+                f,
+                "lines must be between 2 cells long and the width of a board, not {length}"
+            ),
+            BadGeometry::Side(side) => write!(
+                f,
+                "the whole board can be at most {} tiles across, not {side}",
+                Geometry::MAX_SIDE
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BadGeometry {}
+
+impl Geometry {
+    pub const STANDARD: Geometry = Geometry {
+        size: 3,
+        depth: 2,
+        win_length: 3,
+    };
+
+    pub const MAX_SIZE: usize = 5;
+    pub const MAX_DEPTH: usize = 3;
+    /// The most tiles across the whole board, so that it still fits on a screen.
+    pub const MAX_SIDE: usize = 27;
+
+    /// The most levels boards of `size` cells across can have without being too big.
+    pub fn max_depth(size: usize) -> usize {
+        (1..=Self::MAX_DEPTH)
+            .take_while(|&depth| size.pow(depth as u32) <= Self::MAX_SIDE)
+            .last()
+            .unwrap_or(1)
+    }
+
+    pub fn check(self) -> Result<(), BadGeometry> {
+        if !(2..=Self::MAX_SIZE).contains(&self.size) {
+            Err(BadGeometry::Size(self.size))
+        } else if !(1..=Self::MAX_DEPTH).contains(&self.depth) {
+            Err(BadGeometry::Depth(self.depth))
+        } else if !(2..=self.size).contains(&self.win_length) {
+            Err(BadGeometry::WinLength(self.win_length))
+        } else if self.side() > Self::MAX_SIDE {
+            Err(BadGeometry::Side(self.side()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// The number of cells in one board.
+    pub fn cells(self) -> usize {
+        self.size * self.size
+    }
+
+    /// The number of tiles along each edge of the whole board.
+    pub fn side(self) -> usize {
+        self.size.pow(self.depth as u32)
+    }
+
+    pub fn tile_count(self) -> usize {
+        self.side() * self.side()
+    }
+
+    /// The cell `coord` is in at each level, outer board first. Tiles are numbered left to right,
+    /// top to bottom across the whole board, and cells the same way within a board.
+    pub fn path(self, coord: usize) -> Vec<usize> {
+        let (row, column) = (coord / self.side(), coord % self.side());
+        (0..self.depth)
+            .rev()
+            .map(|level| {
+                let scale = self.size.pow(level as u32);
+                (row / scale % self.size) * self.size + column / scale % self.size
+            })
+            .collect()
+    }
+
+    /// The tile at the end of a full path, the inverse of [`Geometry::path`].
+    pub fn coord_of_path(self, path: &[usize]) -> usize {
+        debug_assert_eq!(path.len(), self.depth);
+        let (mut row, mut column) = (0, 0);
+        for &cell in path {
+            row = row * self.size + cell / self.size;
+            column = column * self.size + cell % self.size;
+        }
+        row * self.side() + column
+    }
+
+    /// Every line of `win_length` cells within one board.
+    fn lines(self) -> Vec<Vec<usize>> {
+        let n = self.size as isize;
+        let length = self.win_length as isize;
+        let mut lines = Vec::new();
+        for (dy, dx) in [(0, 1), (1, 0), (1, 1), (1, -1)] {
+            for y in 0..n {
+                for x in 0..n {
+                    let (end_y, end_x) = (y + dy * (length - 1), x + dx * (length - 1));
+                    if (0..n).contains(&end_y) && (0..n).contains(&end_x) {
+                        let line = (0..length)
+                            .map(|i| ((y + dy * i) * n + x + dx * i) as usize)
+                            .collect();
+                        lines.push(line);
+                    }
+                }
+            }
+        }
+        lines
+    }
+}
+
+impl Default for Geometry {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+/// A game on any [`Geometry`]. Slower than [`Position`](crate::Position), and without its
+/// history-independent equality or hashing.
+#[derive(Clone, Debug)]
+pub struct NestedPosition {
+    geometry: Geometry,
+    rules: Ruleset,
+    lines: Vec<Vec<usize>>,
+    tiles: Vec<Option<Player>>,
+    /// `boards[k - 1]` holds the boards whose paths are `k` cells long, for `k` in `1..depth`,
+    /// indexed by reading the path as a number in base `cells`. The outer board is `outcome`.
+    boards: Vec<Vec<MinisquareState>>,
+    side_to_move: Player,
+    outcome: GameOutcome,
+    history: Vec<usize>,
+    /// For each move in `history`, the boards it decided, as `(path length, index)`.
+    decided: Vec<Vec<(usize, usize)>>,
+    /// Moves taken back with `undo`, most recently undone last.
+    undone: Vec<usize>,
+}
+
+impl NestedPosition {
+    /// An empty board with cross to move.
+    pub fn new(geometry: Geometry, rules: Ruleset) -> Result<Self, BadGeometry> {
+        geometry.check()?;
+        let boards = (1..geometry.depth)
+            .map(|k| vec![MinisquareState::Open; geometry.cells().pow(k as u32)])
+            .collect();
+        Ok(NestedPosition {
+            geometry,
+            rules,
+            lines: geometry.lines(),
+            tiles: vec![None; geometry.tile_count()],
+            boards,
+            side_to_move: Player::Cross,
+            outcome: GameOutcome::Ongoing,
+            history: Vec::new(),
+            decided: Vec::new(),
+            undone: Vec::new(),
+        })
+    }
+
+    pub fn geometry(&self) -> Geometry {
+        self.geometry
+    }
+
+    pub fn rules(&self) -> Ruleset {
+        self.rules
+    }
+
+    pub fn tile(&self, coord: usize) -> Option<Player> {
+        self.tiles[coord]
+    }
+
+    /// The state of the board at the end of `path`. The empty path is the outer board, which is
+    /// only ever open or won, since the outcome says how a game without a line ended.
+    pub fn board(&self, path: &[usize]) -> MinisquareState {
+        if path.is_empty() {
+            return match self.outcome {
+                GameOutcome::Win(winner, Termination::OuterLine) => MinisquareState::Won(winner),
+                _ => MinisquareState::Open,
+            };
+        }
+        self.boards[path.len() - 1][self.board_index(path)]
+    }
+
+    /// The player to move, or `None` if the game has ended.
+    pub fn whose_turn(&self) -> Option<Player> {
+        (!self.outcome.is_over()).then_some(self.side_to_move)
+    }
+
+    pub fn side_to_move(&self) -> Player {
+        self.side_to_move
+    }
+
+    pub fn outcome(&self) -> GameOutcome {
+        self.outcome
+    }
+
+    pub fn history(&self) -> &[usize] {
+        &self.history
+    }
+
+    fn board_index(&self, path: &[usize]) -> usize {
+        path.iter()
+            .fold(0, |index, &cell| index * self.geometry.cells() + cell)
+    }
+
+    /// What's in `cell` of the board at `path`: a tile, or a smaller board.
+    fn cell(&self, path: &[usize], cell: usize) -> MinisquareState {
+        let mut path = path.to_vec();
+        path.push(cell);
+        if path.len() == self.geometry.depth {
+            match self.tiles[self.geometry.coord_of_path(&path)] {
+                Some(player) => MinisquareState::Won(player),
+                None => MinisquareState::Open,
+            }
+        } else {
+            self.board(&path)
+        }
+    }
+
+    fn tiles_under(&self, path: &[usize]) -> Vec<usize> {
+        if path.len() == self.geometry.depth {
+            return vec![self.geometry.coord_of_path(path)];
+        }
+        (0..self.geometry.cells())
+            .flat_map(|cell| {
+                let mut path = path.to_vec();
+                path.push(cell);
+                self.tiles_under(&path)
+            })
+            .collect()
+    }
+
+    fn is_full(&self, path: &[usize]) -> bool {
+        self.tiles_under(path)
+            .into_iter()
+            .all(|coord| self.tiles[coord].is_some())
+    }
+
+    /// Whether moves can still go in the board at `path`, depending on the rules. See
+    /// [`Position::is_open`](crate::Position::is_open).
+    pub fn is_open(&self, path: &[usize]) -> bool {
+        if self.rules.play_in_won_minisquares {
+            !self.is_full(path)
+        } else {
+            (1..=path.len()).all(|k| self.board(&path[..k]) == MinisquareState::Open)
+        }
+    }
+
+    /// The path of the board the player to move has to play in. If it's a smallest board, all of
+    /// its empty tiles are legal. Otherwise, they can play in any open board inside it.
+    pub fn forced_board(&self) -> Vec<usize> {
+        let Some(&last) = self.history.last() else {
+            return Vec::new();
+        };
+        let target = &self.geometry.path(last)[1..];
+        (1..=target.len())
+            .rev()
+            .map(|k| &target[..k])
+            .find(|path| self.is_open(path))
+            .unwrap_or_default()
+            .to_vec()
+    }
+
+    pub fn is_legal(&self, coord: usize) -> bool {
+        self.check(coord).is_ok()
+    }
+
+    pub fn check(&self, coord: usize) -> Result<(), IllegalMove> {
+        self.check_in(coord, &self.forced_board())
+    }
+
+    fn check_in(&self, coord: usize, forced: &[usize]) -> Result<(), IllegalMove> {
+        if self.outcome.is_over() {
+            return Err(IllegalMove::GameOver);
+        }
+        if coord >= self.tiles.len() {
+            return Err(IllegalMove::OutOfRange);
+        }
+        if self.tiles[coord].is_some() {
+            return Err(IllegalMove::Occupied);
+        }
+        let path = self.geometry.path(coord);
+        if !path.starts_with(forced) {
+            return Err(IllegalMove::WrongBoard);
+        }
+        let smallest_board = &path[..self.geometry.depth - 1];
+        if !self.rules.free_move_anywhere && !self.is_open(smallest_board) {
+            return Err(IllegalMove::ClosedBoard);
+        }
+        Ok(())
+    }
+
+    pub fn legal_moves(&self) -> Vec<usize> {
+        let forced = self.forced_board();
+        (0..self.tiles.len())
+            .filter(|&coord| self.check_in(coord, &forced).is_ok())
+            .collect()
+    }
+
+    /// Plays a move for whoever's turn it is. This forgets any moves that could have been redone.
+    pub fn try_play(&mut self, coord: usize) -> Result<(), IllegalMove> {
+        self.check(coord)?;
+        self.undone.clear();
+        self.apply(coord);
+        Ok(())
+    }
+
+    /// Takes back the last move, returning where it was played.
+    pub fn undo(&mut self) -> Option<usize> {
+        let coord = self.history.pop()?;
+        for (k, index) in self.decided.pop().unwrap() {
+            self.boards[k - 1][index] = MinisquareState::Open;
+        }
+        self.side_to_move = self.tiles[coord].take().unwrap();
+        self.outcome = GameOutcome::Ongoing;
+        self.undone.push(coord);
+        Some(coord)
+    }
+
+    /// Replays the last move taken back with `undo`.
+    pub fn redo(&mut self) -> Option<usize> {
+        let coord = self.undone.pop()?;
+        self.apply(coord);
+        Some(coord)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.history.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    fn has_line(&self, path: &[usize], player: Player) -> bool {
+        self.lines.iter().any(|line| {
+            line.iter()
+                .all(|&cell| self.cell(path, cell) == MinisquareState::Won(player))
+        })
+    }
+
+    /// Plays a move that has already been checked.
+    fn apply(&mut self, coord: usize) {
+        let us = self.side_to_move;
+        self.tiles[coord] = Some(us);
+        let path = self.geometry.path(coord);
+
+        // a board can only be decided if the cell that changed in it was decided, so work
+        // outwards until a board doesn't change
+        let mut decided = Vec::new();
+        for k in (1..self.geometry.depth).rev() {
+            let board = &path[..k];
+            if self.board(board).is_decided() {
+                // it stays with whoever won it first
+                break;
+            }
+            let state = if self.has_line(board, us) {
+                MinisquareState::Won(us)
+            } else if (0..self.geometry.cells()).all(|cell| self.cell(board, cell).is_decided()) {
+                MinisquareState::Drawn
+            } else {
+                break;
+            };
+            let index = self.board_index(board);
+            self.boards[k - 1][index] = state;
+            decided.push((k, index));
+        }
+        self.decided.push(decided);
+        self.history.push(coord);
+        self.side_to_move = !us;
+
+        if self.has_line(&[], us) {
+            self.outcome = GameOutcome::Win(us, Termination::OuterLine);
+        } else if self.legal_moves().is_empty() {
+            self.outcome = self.outcome_without_line();
+        }
+    }
+
+    /// How the game ends when nobody can move.
+    fn outcome_without_line(&self) -> GameOutcome {
+        let won_by = |player| {
+            (0..self.geometry.cells())
+                .filter(|&cell| self.cell(&[], cell) == MinisquareState::Won(player))
+                .count()
+        };
+        GameOutcome::without_line(self.rules, won_by(Player::Nought), won_by(Player::Cross))
+    }
+}
@@ -0,0 +1,96 @@
+//! Writing moves down for people to read.
+//!
+//! A move can be written globally, as a column `A`–`I` from the left and a row `1`–`9` from the
+//! top (so the centre tile is `E5`), or relative to its minisquare, as the compass direction of
+//! the minisquare and then of the tile within it (so the centre tile is `C/c`, and the top-left
+//! tile is `NW/nw`). Both forms are accepted when parsing, in either case.
+
+use std::fmt;
+
+use crate::{coord_of, minisquare_of, target_of};
+
+const DIRECTIONS: [&str; 9] = ["NW", "N", "NE", "W", "C", "E", "SW", "S", "SE"];
+
+/// Displays a move as e.g. `E5`.
+#[derive(Clone, Copy, Debug)]
+pub struct Global(pub usize);
+
+impl fmt::Display for Global {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let column = (b'A' + (self.0 % 9) as u8) as char;
+        write!(f, "{column}{}", self.0 / 9 + 1)
+    }
+}
+
+/// Displays a move as e.g. `NW/c`.
+#[derive(Clone, Copy, Debug)]
+pub struct Relative(pub usize);
+
+impl fmt::Display for Relative {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inner = DIRECTIONS[target_of(self.0)].to_ascii_lowercase();
+        write!(f, "{}/{inner}", Minisquare(minisquare_of(self.0)))
+    }
+}
+
+/// Displays a minisquare index as e.g. `NW`.
+#[derive(Clone, Copy, Debug)]
+pub struct Minisquare(pub usize);
+
+impl fmt::Display for Minisquare {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(DIRECTIONS[self.0])
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseMoveError {
+    /// Not a column and row, or two directions separated by `/`.
+    BadFormat(String),
+    /// The column or row is past the edge of the board.
+    OutOfRange(String),
+    /// Not one of `NW`, `N`, `NE`, `W`, `C`, `E`, `SW`, `S` or `SE`.
+    BadDirection(String),
+}
+
+impl fmt::Display for ParseMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseMoveError::BadFormat(s) => write!(f, "{s:?} isn't a move like E5 or NW/c"),
+            ParseMoveError::OutOfRange(s) => write!(f, "{s:?} is off the edge of the board"),
+            ParseMoveError::BadDirection(s) => write!(f, "{s:?} isn't a compass direction"),
+        }
+    }
+}
+
+impl std::error::Error for ParseMoveError {}
+
+/// Parses a minisquare written as e.g. `NW`.
+pub fn parse_minisquare(s: &str) -> Result<usize, ParseMoveError> {
+    DIRECTIONS
+        .iter()
+        .position(|direction| direction.eq_ignore_ascii_case(s))
+        .ok_or_else(|| ParseMoveError::BadDirection(s.to_owned()))
+}
+
+/// Parses a move in either the global or relative form.
+pub fn parse_move(s: &str) -> Result<usize, ParseMoveError> {
+    if let Some((outer, inner)) = s.split_once('/') {
+        let minisquare = parse_minisquare(outer)?;
+        let target = parse_minisquare(inner)?;
+        return Ok(coord_of(minisquare, target));
+    }
+
+    let mut chars = s.chars();
+    let (Some(column), Ok(row)) = (chars.next(), chars.as_str().parse::<usize>()) else {
+        return Err(ParseMoveError::BadFormat(s.to_owned()));
+    };
+    if !column.is_ascii_alphabetic() || !chars.as_str().bytes().all(|b| b.is_ascii_digit()) {
+        return Err(ParseMoveError::BadFormat(s.to_owned()));
+    }
+    let column = (column.to_ascii_uppercase() as u8 - b'A') as usize;
+    if column >= 9 || !(1..=9).contains(&row) {
+        return Err(ParseMoveError::OutOfRange(s.to_owned()));
+    }
+    Ok((row - 1) * 9 + column)
+}
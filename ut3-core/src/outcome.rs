@@ -0,0 +1,100 @@
+use std::{fmt, str::FromStr};
+
+use crate::{Player, Ruleset};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GameOutcome {
+    Ongoing,
+    Win(Player, Termination),
+    Draw(Termination),
+}
+
+impl GameOutcome {
+    pub fn is_over(self) -> bool {
+        self != GameOutcome::Ongoing
+    }
+
+    pub fn winner(self) -> Option<Player> {
+        match self {
+            GameOutcome::Win(player, _) => Some(player),
+            _ => None,
+        }
+    }
+
+    /// How a game ends when nobody can move, given how many minisquares each player won.
+    pub(crate) fn without_line(rules: Ruleset, noughts: usize, crosses: usize) -> GameOutcome {
+        if !rules.minisquare_tiebreak || noughts == crosses {
+            GameOutcome::Draw(Termination::NoMovesLeft)
+        } else if noughts > crosses {
+            GameOutcome::Win(Player::Nought, Termination::MinisquareCount)
+        } else {
+            GameOutcome::Win(Player::Cross, Termination::MinisquareCount)
+        }
+    }
+
+    pub fn termination(self) -> Option<Termination> {
+        match self {
+            GameOutcome::Ongoing => None,
+            GameOutcome::Win(_, termination) | GameOutcome::Draw(termination) => Some(termination),
+        }
+    }
+}
+
+/// Why a game ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Termination {
+    /// Someone won three minisquares in a row on the outer board.
+    OuterLine,
+    /// The player to move has nowhere to play.
+    NoMovesLeft,
+    /// Nobody could move, and the winner won more minisquares.
+    /// See [`Ruleset::minisquare_tiebreak`](crate::Ruleset::minisquare_tiebreak).
+    MinisquareCount,
+    /// The loser ran out of time. See [`Clock`](crate::clock::Clock).
+    Timeout,
+}
+
+impl Termination {
+    const ALL: [Termination; 4] = [
+        Termination::OuterLine,
+        Termination::NoMovesLeft,
+        Termination::MinisquareCount,
+        Termination::Timeout,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Termination::OuterLine => "outer line",
+            Termination::NoMovesLeft => "no moves left",
+            Termination::MinisquareCount => "minisquare count",
+            Termination::Timeout => "timeout",
+        }
+    }
+}
+
+impl fmt::Display for Termination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownTermination(pub String);
+
+impl fmt::Display for UnknownTermination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown termination {:?}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownTermination {}
+
+impl FromStr for Termination {
+    type Err = UnknownTermination;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Termination::ALL
+            .into_iter()
+            .find(|termination| termination.name() == s)
+            .ok_or_else(|| UnknownTermination(s.to_owned()))
+    }
+}
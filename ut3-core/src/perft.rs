@@ -0,0 +1,76 @@
+//! Counting the positions reachable in a fixed number of moves, to check move generation against
+//! [`Position`], which is slower but much easier to read.
+
+use crate::{BitPosition, Position};
+
+/// The number of move sequences exactly `depth` moves long from `position`. Games that end early
+/// don't count.
+pub fn perft(position: &mut BitPosition, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = position.legal_moves();
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    let mut nodes = 0;
+    for &coord in moves.iter() {
+        let unmake = position.make(coord as usize);
+        nodes += perft(position, depth - 1);
+        position.unmake(coord as usize, unmake);
+    }
+    nodes
+}
+
+/// [`perft`] split up by the first move, so a wrong count can be narrowed down to a line.
+pub fn divide(position: &mut BitPosition, depth: u32) -> Vec<(usize, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+    let moves = position.legal_moves();
+    moves
+        .iter()
+        .map(|&coord| {
+            let unmake = position.make(coord as usize);
+            let nodes = perft(position, depth - 1);
+            position.unmake(coord as usize, unmake);
+            (coord as usize, nodes)
+        })
+        .collect()
+}
+
+/// [`perft`] using [`Position`] instead.
+pub fn perft_reference(position: &mut Position, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves: Vec<usize> = position.legal_moves().collect();
+    let mut nodes = 0;
+    for coord in moves {
+        position
+            .try_play(coord)
+            .expect("legal_moves gave an illegal move");
+        nodes += perft_reference(position, depth - 1);
+        position.undo();
+    }
+    nodes
+}
+
+/// [`divide`] using [`Position`] instead.
+pub fn divide_reference(position: &mut Position, depth: u32) -> Vec<(usize, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+    let moves: Vec<usize> = position.legal_moves().collect();
+    moves
+        .into_iter()
+        .map(|coord| {
+            position
+                .try_play(coord)
+                .expect("legal_moves gave an illegal move");
+            let nodes = perft_reference(position, depth - 1);
+            position.undo();
+            (coord, nodes)
+        })
+        .collect()
+}
@@ -0,0 +1,427 @@
+use crate::{symmetry::Symmetry, zobrist, GameOutcome, IllegalMove, Player, Ruleset, Termination};
+
+/// Every line of three on a 3×3 board, indexed left to right, top to bottom.
+pub(crate) const LINES: [[usize; 3]; 8] = [
+    [0, 1, 2],
+    [3, 4, 5],
+    [6, 7, 8],
+    [0, 3, 6],
+    [1, 4, 7],
+    [2, 5, 8],
+    [0, 4, 8],
+    [2, 4, 6],
+];
+
+/// Whether a 3×3 board has a line of squares for which `owned` is true.
+pub(crate) fn has_line(owned: impl Fn(usize) -> bool) -> bool {
+    LINES.iter().any(|line| line.iter().all(|&i| owned(i)))
+}
+
+/// The minisquare that the tile at `coord` belongs to.
+pub const fn minisquare_of(coord: usize) -> usize {
+    (coord / 27) * 3 + (coord % 9) / 3
+}
+
+/// The tile at `cell` (numbered like minisquares) within `minisquare`.
+pub const fn coord_of(minisquare: usize, cell: usize) -> usize {
+    (minisquare / 3) * 27 + (cell / 3) * 9 + (minisquare % 3) * 3 + cell % 3
+}
+
+/// The minisquare that playing at `coord` sends the opponent to.
+pub const fn target_of(coord: usize) -> usize {
+    ((coord / 9) % 3) * 3 + coord % 3
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MinisquareState {
+    Open,
+    Won(Player),
+    /// Every tile is taken but nobody got a line.
+    Drawn,
+}
+
+impl MinisquareState {
+    /// Whether someone has won it or it has been drawn. Whether it can still be played in depends
+    /// on the [`Ruleset`].
+    pub fn is_decided(self) -> bool {
+        self != MinisquareState::Open
+    }
+
+    pub fn winner(self) -> Option<Player> {
+        match self {
+            MinisquareState::Won(player) => Some(player),
+            _ => None,
+        }
+    }
+}
+
+/// What happened as a result of a move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MoveEffects {
+    pub player: Player,
+    pub coord: usize,
+    /// The minisquare played in and its new state, if the move won or drew it.
+    ///
+    /// This is the minisquare being decided, so it might still be possible to play there
+    /// depending on the [`Ruleset`].
+    pub closed_minisquare: Option<(usize, MinisquareState)>,
+    pub outcome: GameOutcome,
+}
+
+/// A game in progress. Two positions are equal if the rules, the board, whose turn it is and
+/// where they have to play are the same, no matter how they got there.
+#[derive(Clone, Debug)]
+pub struct Position {
+    rules: Ruleset,
+    tiles: [Option<Player>; 81],
+    minisquares: [MinisquareState; 9],
+    side_to_move: Player, // still set once the game has ended
+    outcome: GameOutcome,
+    history: Vec<usize>, // coord
+    /// For each move in `history`, the state its minisquare was in before it was played.
+    previous_minisquares: Vec<MinisquareState>,
+    /// Moves taken back with `undo`, most recently undone last.
+    undone: Vec<usize>,
+    /// Where the first player to move was sent, for positions that didn't start out empty.
+    initial_forced_board: Option<usize>,
+}
+
+impl PartialEq for Position {
+    fn eq(&self, other: &Self) -> bool {
+        self.rules == other.rules
+            && self.tiles == other.tiles
+            && self.minisquares == other.minisquares
+            && self.side_to_move == other.side_to_move
+            && self.forced_board() == other.forced_board()
+            && self.outcome == other.outcome
+    }
+}
+
+impl Eq for Position {}
+
+impl std::hash::Hash for Position {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        state.write_u64(self.zobrist());
+    }
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Position {
+    /// An empty board with cross to move, using the standard rules.
+    pub fn new() -> Self {
+        Self::with_rules(Ruleset::STANDARD)
+    }
+
+    /// An empty board with cross to move.
+    pub fn with_rules(rules: Ruleset) -> Self {
+        Position {
+            rules,
+            tiles: [None; 81],
+            minisquares: [MinisquareState::Open; 9],
+            side_to_move: Player::Cross,
+            outcome: GameOutcome::Ongoing,
+            history: Vec::new(),
+            previous_minisquares: Vec::new(),
+            undone: Vec::new(),
+            initial_forced_board: None,
+        }
+    }
+
+    /// A position partway through a game, with no history. The parts must have already been
+    /// checked to make sense together.
+    pub(crate) fn from_parts(
+        rules: Ruleset,
+        tiles: [Option<Player>; 81],
+        minisquares: [MinisquareState; 9],
+        side_to_move: Player,
+        forced_board: Option<usize>,
+    ) -> Self {
+        let mut position = Position {
+            tiles,
+            minisquares,
+            side_to_move,
+            initial_forced_board: forced_board,
+            ..Self::with_rules(rules)
+        };
+        position.outcome = [Player::Cross, Player::Nought]
+            .into_iter()
+            .find(|&player| has_line(|i| minisquares[i] == MinisquareState::Won(player)))
+            .map(|winner| GameOutcome::Win(winner, Termination::OuterLine))
+            .unwrap_or(GameOutcome::Ongoing);
+        if !position.outcome.is_over() && position.legal_moves().next().is_none() {
+            position.outcome = position.outcome_without_line();
+        }
+        position
+    }
+
+    pub fn rules(&self) -> Ruleset {
+        self.rules
+    }
+
+    pub fn tiles(&self) -> &[Option<Player>; 81] {
+        &self.tiles
+    }
+
+    pub fn tile(&self, coord: usize) -> Option<Player> {
+        self.tiles[coord]
+    }
+
+    pub fn minisquares(&self) -> &[MinisquareState; 9] {
+        &self.minisquares
+    }
+
+    pub fn minisquare(&self, index: usize) -> MinisquareState {
+        self.minisquares[index]
+    }
+
+    /// The player to move, or `None` if the game has ended.
+    pub fn whose_turn(&self) -> Option<Player> {
+        (!self.outcome.is_over()).then_some(self.side_to_move)
+    }
+
+    /// The player whose turn it is, or would be if the game hadn't ended.
+    pub fn side_to_move(&self) -> Player {
+        self.side_to_move
+    }
+
+    pub fn outcome(&self) -> GameOutcome {
+        self.outcome
+    }
+
+    /// A 64-bit hash of everything that [`PartialEq`] compares. It's stable between runs, so can
+    /// be stored or sent to another machine.
+    pub fn zobrist(&self) -> u64 {
+        let tiles = self
+            .tiles
+            .iter()
+            .enumerate()
+            .filter_map(|(coord, tile)| Some(zobrist::tile(coord, (*tile)?)));
+        let minisquares = (self.minisquares.iter().enumerate())
+            .map(|(index, state)| zobrist::minisquare(index, *state));
+        tiles.chain(minisquares).fold(
+            zobrist::side_to_move(self.side_to_move) ^ zobrist::forced_board(self.forced_board()),
+            |hash, key| hash ^ key,
+        )
+    }
+
+    /// Every move played so far, in order.
+    pub fn history(&self) -> &[usize] {
+        &self.history
+    }
+
+    /// Whether the player to move may play at `coord`.
+    pub fn is_legal(&self, coord: usize) -> bool {
+        self.check(coord).is_ok()
+    }
+
+    /// Checks whether the player to move may play at `coord`, and if not, why not.
+    pub fn check(&self, coord: usize) -> Result<(), IllegalMove> {
+        if self.outcome.is_over() {
+            return Err(IllegalMove::GameOver);
+        }
+        if coord >= 81 {
+            return Err(IllegalMove::OutOfRange);
+        }
+        if self.tiles[coord].is_some() {
+            return Err(IllegalMove::Occupied);
+        }
+        let current_minisquare = minisquare_of(coord);
+        let forced_board = self.forced_board();
+        // the tile is empty, so the minisquare isn't full
+        let free_move_anywhere = forced_board.is_none() && self.rules.free_move_anywhere;
+        if !self.is_open(current_minisquare) && !free_move_anywhere {
+            return Err(IllegalMove::ClosedBoard);
+        }
+        if forced_board.is_some_and(|forced| forced != current_minisquare) {
+            return Err(IllegalMove::WrongBoard);
+        }
+        Ok(())
+    }
+
+    /// Whether the rules allow playing in a minisquare, as long as the player has been sent there.
+    pub fn is_open(&self, minisquare: usize) -> bool {
+        match self.minisquares[minisquare] {
+            MinisquareState::Open => true,
+            MinisquareState::Won(_) => {
+                self.rules.play_in_won_minisquares && !self.is_full(minisquare)
+            }
+            MinisquareState::Drawn => false,
+        }
+    }
+
+    pub(crate) fn is_full(&self, minisquare: usize) -> bool {
+        let topleft = (minisquare / 3) * 27 + (minisquare % 3) * 3;
+        (0..3)
+            .flat_map(|y| topleft + y * 9..topleft + y * 9 + 3)
+            .all(|tile| self.tiles[tile].is_some())
+    }
+
+    /// The minisquare the player to move has been sent to, or `None` if they can play in any open
+    /// minisquare.
+    pub fn forced_board(&self) -> Option<usize> {
+        let Some(&last_move) = self.history.last() else {
+            return self.initial_forced_board;
+        };
+        let target = target_of(last_move);
+        self.is_open(target).then_some(target)
+    }
+
+    /// Every tile the player to move may play on, in ascending order.
+    pub fn legal_moves(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..81).filter(|&coord| self.is_legal(coord))
+    }
+
+    /// Plays a move for `player`, who must be the player to move.
+    pub fn try_play_as(
+        &mut self,
+        player: Player,
+        coord: usize,
+    ) -> Result<MoveEffects, IllegalMove> {
+        if self.whose_turn().is_some_and(|us| us != player) {
+            return Err(IllegalMove::NotYourTurn);
+        }
+        self.try_play(coord)
+    }
+
+    /// Plays a move for whoever's turn it is. This forgets any moves that could have been redone.
+    pub fn try_play(&mut self, coord: usize) -> Result<MoveEffects, IllegalMove> {
+        self.check(coord)?;
+        self.undone.clear();
+        Ok(self.apply(coord))
+    }
+
+    /// Takes back the last move, returning where it was played.
+    pub fn undo(&mut self) -> Option<usize> {
+        let coord = self.history.pop()?;
+        let previous = self.previous_minisquares.pop().unwrap();
+        self.side_to_move = self.tiles[coord].take().unwrap();
+        self.minisquares[minisquare_of(coord)] = previous;
+        // a move was played from here, so the game can't have been over
+        self.outcome = GameOutcome::Ongoing;
+        self.undone.push(coord);
+        Some(coord)
+    }
+
+    /// Ends the game because `player` ran out of time. Does nothing if it's already over.
+    pub fn time_out(&mut self, player: Player) {
+        if !self.outcome.is_over() {
+            self.outcome = GameOutcome::Win(!player, Termination::Timeout);
+        }
+    }
+
+    /// Replays the last move taken back with `undo`.
+    pub fn redo(&mut self) -> Option<MoveEffects> {
+        let coord = self.undone.pop()?;
+        Some(self.apply(coord))
+    }
+
+    /// This position with the board rotated or reflected. The history and any moves that could
+    /// be redone are transformed to match, so undo and redo still work.
+    pub fn transformed(&self, symmetry: Symmetry) -> Position {
+        let mut tiles = [None; 81];
+        for (coord, &tile) in self.tiles.iter().enumerate() {
+            tiles[symmetry.apply(coord)] = tile;
+        }
+        let mut minisquares = [MinisquareState::Open; 9];
+        for (index, &state) in self.minisquares.iter().enumerate() {
+            minisquares[symmetry.apply_to_minisquare(index)] = state;
+        }
+        Position {
+            rules: self.rules,
+            tiles,
+            minisquares,
+            side_to_move: self.side_to_move,
+            // lines are still lines after any symmetry
+            outcome: self.outcome,
+            history: symmetry.apply_to_history(&self.history),
+            previous_minisquares: self.previous_minisquares.clone(),
+            undone: symmetry.apply_to_history(&self.undone),
+            initial_forced_board: self
+                .initial_forced_board
+                .map(|index| symmetry.apply_to_minisquare(index)),
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.history.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Plays a move that has already been checked.
+    fn apply(&mut self, coord: usize) -> MoveEffects {
+        let us = self.side_to_move;
+        self.tiles[coord] = Some(us);
+
+        // did we win a minisquare?
+        let inner_x = coord % 3;
+        let inner_y = (coord / 9) % 3;
+        let minisquare = minisquare_of(coord);
+        self.previous_minisquares.push(self.minisquares[minisquare]);
+        let topleft = (minisquare / 3) * 27 + (minisquare % 3) * 3;
+        let won_axis = (self.tiles[topleft + inner_y * 9] == Some(us)
+            && self.tiles[topleft + inner_y * 9 + 1] == Some(us)
+            && self.tiles[topleft + inner_y * 9 + 2] == Some(us))
+            || (self.tiles[topleft + inner_x] == Some(us)
+                && self.tiles[topleft + 9 + inner_x] == Some(us)
+                && self.tiles[topleft + 2 * 9 + inner_x] == Some(us));
+        // the centre is on both diagonals, so check each one separately
+        let won_diagonal = (inner_x == inner_y
+            && (0..3).all(|i| self.tiles[topleft + i * 9 + i] == Some(us)))
+            || (inner_x + inner_y == 2
+                && (0..3).all(|i| self.tiles[topleft + i * 9 + (2 - i)] == Some(us)));
+        // a minisquare that's already been won stays with whoever won it first
+        let mut closed_minisquare = None;
+        if self.minisquares[minisquare] == MinisquareState::Open {
+            if won_axis || won_diagonal {
+                closed_minisquare = Some((minisquare, MinisquareState::Won(us)));
+            } else if self.is_full(minisquare) {
+                closed_minisquare = Some((minisquare, MinisquareState::Drawn));
+            }
+        }
+        if let Some((_, state)) = closed_minisquare {
+            self.minisquares[minisquare] = state;
+        }
+
+        // did we win the game? drawn minisquares count for nobody
+        let won = |i: usize| self.minisquares[i] == MinisquareState::Won(us);
+        let mut won_axis = (0..3).any(|y| won(y * 3) && won(y * 3 + 1) && won(y * 3 + 2));
+        for i in 0..3 {
+            won_axis |= won(i) && won(i + 3) && won(i + 6);
+        }
+        let won_diagonal = won(4) && (won(0) && won(8) || won(2) && won(6));
+
+        self.history.push(coord);
+        self.side_to_move = !us;
+
+        if won_axis || won_diagonal {
+            self.outcome = GameOutcome::Win(us, Termination::OuterLine);
+        } else if self.legal_moves().next().is_none() {
+            self.outcome = self.outcome_without_line();
+        }
+
+        MoveEffects {
+            player: us,
+            coord,
+            closed_minisquare,
+            outcome: self.outcome,
+        }
+    }
+
+    /// How the game ends when nobody can move.
+    fn outcome_without_line(&self) -> GameOutcome {
+        let won_by = |player| {
+            (self.minisquares.iter())
+                .filter(|state| state.winner() == Some(player))
+                .count()
+        };
+        GameOutcome::without_line(self.rules, won_by(Player::Nought), won_by(Player::Cross))
+    }
+}
@@ -0,0 +1,339 @@
+//! A text format for whole games, modelled on chess's PGN: some headers in square brackets, a
+//! blank line, then the moves in [global notation](crate::notation::Global) with move numbers.
+//!
+//! ```text
+//! [Cross "Alice"]
+//! [Nought "Bob"]
+//! [Date "2026.10.18"]
+//! [Rules "standard"]
+//! [Result "1-0"]
+//! [Termination "outer line"]
+//!
+//! 1. E5 E4 2. E2 D5 ... 1-0
+//! ```
+//!
+//! The result is `1-0` if cross won, `0-1` if nought won, `1/2-1/2` for a draw and `*` for a game
+//! that hasn't finished. A `Position` header holds a [position string](crate::fen) for games that
+//! didn't start from an empty board. Parsing replays the moves through the rules, so a record that
+//! parses is always a legal game.
+
+use std::{fmt, str::FromStr};
+
+use crate::{
+    fen::ParsePositionError,
+    notation::{self, ParseMoveError},
+    GameOutcome, IllegalMove, Player, Position, Ruleset, Termination,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameRecord {
+    pub cross: Option<String>,
+    pub nought: Option<String>,
+    /// Written as `YYYY.MM.DD`, like PGN.
+    pub date: Option<String>,
+    pub rules: Ruleset,
+    pub time_control: Option<String>,
+    /// The position string the game started from, if it wasn't an empty board.
+    pub start: Option<String>,
+    pub outcome: GameOutcome,
+    pub moves: Vec<usize>,
+    /// Headers this module doesn't know about, kept so that they get written back out.
+    pub other_headers: Vec<(String, String)>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RecordError {
+    /// A line in the header section that isn't `[Name "value"]`.
+    BadHeader {
+        line: usize,
+        text: String,
+    },
+    BadHeaderValue {
+        name: String,
+        value: String,
+    },
+    BadPosition(ParsePositionError),
+    /// `ply` counts from 1 for the first move in the record.
+    BadMove {
+        ply: usize,
+        text: String,
+        error: ParseMoveError,
+    },
+    IllegalMove {
+        ply: usize,
+        text: String,
+        error: IllegalMove,
+    },
+    /// The headers say the game ended differently to how the moves do.
+    WrongResult {
+        recorded: GameOutcome,
+        actual: GameOutcome,
+    },
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordError::BadHeader { line, text } => {
+                write!(
+                    f,
+                    "line {line}: {text:?} isn't a header like [Name \"value\"]"
+                )
+            }
+            RecordError::BadHeaderValue { name, value } => {
+                write!(f, "bad value {value:?} for header {name}")
+            }
+            RecordError::BadPosition(e) => write!(f, "bad starting position: {e}"),
+            RecordError::BadMove { ply, error, .. } => write!(f, "move {ply}: {error}"),
+            RecordError::IllegalMove { ply, text, error } => {
+                write!(f, "move {ply} ({text}) is illegal: {error}")
+            }
+            RecordError::WrongResult { recorded, actual } => write!(
+                f,
+                "the headers say the result is {recorded:?}, but the moves give {actual:?}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+impl GameRecord {
+    /// A record of the game that led to `position`, with no names or date.
+    pub fn from_position(position: &Position) -> Self {
+        let mut start = position.clone();
+        while start.undo().is_some() {}
+        let start = (start != Position::with_rules(position.rules())).then(|| start.to_string());
+        GameRecord {
+            cross: None,
+            nought: None,
+            date: None,
+            rules: position.rules(),
+            time_control: None,
+            start,
+            outcome: position.outcome(),
+            moves: position.history().to_vec(),
+            other_headers: Vec::new(),
+        }
+    }
+
+    fn start_position(&self) -> Result<Position, RecordError> {
+        match &self.start {
+            Some(fen) => Position::from_fen(fen, self.rules).map_err(RecordError::BadPosition),
+            None => Ok(Position::with_rules(self.rules)),
+        }
+    }
+
+    /// Plays through the moves, stopping at the first illegal one.
+    pub fn replay(&self) -> Result<Position, RecordError> {
+        let mut position = self.start_position()?;
+        for (i, &coord) in self.moves.iter().enumerate() {
+            position
+                .try_play(coord)
+                .map_err(|error| RecordError::IllegalMove {
+                    ply: i + 1,
+                    text: notation::Global(coord).to_string(),
+                    error,
+                })?;
+        }
+        // running out of time isn't in the moves
+        if let GameOutcome::Win(winner, Termination::Timeout) = self.outcome {
+            position.time_out(!winner);
+        }
+        if position.outcome() != self.outcome {
+            return Err(RecordError::WrongResult {
+                recorded: self.outcome,
+                actual: position.outcome(),
+            });
+        }
+        Ok(position)
+    }
+}
+
+fn result_str(outcome: GameOutcome) -> &'static str {
+    match outcome {
+        GameOutcome::Ongoing => "*",
+        GameOutcome::Win(Player::Cross, _) => "1-0",
+        GameOutcome::Win(Player::Nought, _) => "0-1",
+        GameOutcome::Draw(_) => "1/2-1/2",
+    }
+}
+
+fn write_header(f: &mut fmt::Formatter<'_>, name: &str, value: &str) -> fmt::Result {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    writeln!(f, "[{name} \"{value}\"]")
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let optional = [
+            ("Cross", &self.cross),
+            ("Nought", &self.nought),
+            ("Date", &self.date),
+        ];
+        for (name, value) in optional {
+            if let Some(value) = value {
+                write_header(f, name, value)?;
+            }
+        }
+        write_header(f, "Rules", &self.rules.to_string())?;
+        if let Some(time_control) = &self.time_control {
+            write_header(f, "TimeControl", time_control)?;
+        }
+        if let Some(start) = &self.start {
+            write_header(f, "Position", start)?;
+        }
+        write_header(f, "Result", result_str(self.outcome))?;
+        if let Some(termination) = self.outcome.termination() {
+            write_header(f, "Termination", &termination.to_string())?;
+        }
+        for (name, value) in &self.other_headers {
+            write_header(f, name, value)?;
+        }
+        writeln!(f)?;
+
+        let nought_first = self
+            .start_position()
+            .is_ok_and(|start| start.side_to_move() == Player::Nought);
+        let mut tokens = Vec::new();
+        for (i, &coord) in self.moves.iter().enumerate() {
+            let ply = i + nought_first as usize;
+            if ply.is_multiple_of(2) {
+                tokens.push(format!("{}.", ply / 2 + 1));
+            } else if i == 0 {
+                tokens.push("1...".to_owned());
+            }
+            tokens.push(notation::Global(coord).to_string());
+        }
+        tokens.push(result_str(self.outcome).to_owned());
+
+        // wrap the moves at 80 columns
+        let mut line_len = 0;
+        for token in tokens {
+            if line_len > 0 && line_len + 1 + token.len() > 80 {
+                writeln!(f)?;
+                line_len = 0;
+            } else if line_len > 0 {
+                f.write_str(" ")?;
+                line_len += 1;
+            }
+            f.write_str(&token)?;
+            line_len += token.len();
+        }
+        writeln!(f)
+    }
+}
+
+/// Parses `[Name "value"]`.
+fn parse_header(line: &str) -> Option<(String, String)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?;
+    let (name, quoted) = inner.split_once(' ')?;
+    let quoted = quoted.trim().strip_prefix('"')?.strip_suffix('"')?;
+    let mut value = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => value.push(chars.next()?),
+            '"' => return None,
+            c => value.push(c),
+        }
+    }
+    Some((name.to_owned(), value))
+}
+
+fn parse_outcome(
+    result: Option<&str>,
+    termination: Option<&str>,
+) -> Result<GameOutcome, RecordError> {
+    let bad = |name: &str, value: &str| RecordError::BadHeaderValue {
+        name: name.to_owned(),
+        value: value.to_owned(),
+    };
+    let result = result.unwrap_or("*");
+    let termination = match termination {
+        Some(termination) => Some(
+            termination
+                .parse::<Termination>()
+                .map_err(|_| bad("Termination", termination))?,
+        ),
+        None => None,
+    };
+    let outcome = match (result, termination) {
+        ("*", None) => GameOutcome::Ongoing,
+        ("1-0", Some(termination)) => GameOutcome::Win(Player::Cross, termination),
+        ("0-1", Some(termination)) => GameOutcome::Win(Player::Nought, termination),
+        ("1/2-1/2", Some(termination)) => GameOutcome::Draw(termination),
+        _ => return Err(bad("Result", result)),
+    };
+    Ok(outcome)
+}
+
+impl FromStr for GameRecord {
+    type Err = RecordError;
+
+    /// Parses a record and checks that its moves are legal.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut record = GameRecord {
+            cross: None,
+            nought: None,
+            date: None,
+            rules: Ruleset::STANDARD,
+            time_control: None,
+            start: None,
+            outcome: GameOutcome::Ongoing,
+            moves: Vec::new(),
+            other_headers: Vec::new(),
+        };
+        let mut result = None;
+        let mut termination = None;
+
+        let mut lines = s.lines().enumerate().peekable();
+        while let Some((i, line)) = lines.next_if(|(_, line)| line.trim().starts_with('[')) {
+            let (name, value) =
+                parse_header(line.trim()).ok_or_else(|| RecordError::BadHeader {
+                    line: i + 1,
+                    text: line.to_owned(),
+                })?;
+            match name.as_str() {
+                "Cross" => record.cross = Some(value),
+                "Nought" => record.nought = Some(value),
+                "Date" => record.date = Some(value),
+                "Rules" => {
+                    record.rules = value.parse().map_err(|_| RecordError::BadHeaderValue {
+                        name: name.clone(),
+                        value,
+                    })?
+                }
+                "TimeControl" => record.time_control = Some(value),
+                "Position" => record.start = Some(value),
+                "Result" => result = Some(value),
+                "Termination" => termination = Some(value),
+                _ => record.other_headers.push((name, value)),
+            }
+        }
+        record.outcome = parse_outcome(result.as_deref(), termination.as_deref())?;
+
+        let movetext = lines.map(|(_, line)| line).collect::<Vec<_>>().join(" ");
+        let is_move_number = |token: &str| {
+            let digits = token.trim_end_matches('.');
+            digits.len() < token.len() && digits.bytes().all(|b| b.is_ascii_digit())
+        };
+        let tokens = movetext
+            .split_whitespace()
+            .filter(|token| !is_move_number(token));
+        for token in tokens {
+            if ["*", "1-0", "0-1", "1/2-1/2"].contains(&token) {
+                break;
+            }
+            let coord = notation::parse_move(token).map_err(|error| RecordError::BadMove {
+                ply: record.moves.len() + 1,
+                text: token.to_owned(),
+                error,
+            })?;
+            record.moves.push(coord);
+        }
+
+        record.replay()?;
+        Ok(record)
+    }
+}
@@ -0,0 +1,84 @@
+use std::{fmt, str::FromStr};
+
+/// The rules that differ between groups of players. The default is the most common set: won
+/// minisquares are locked, being sent to one lets you play in any other open minisquare, and
+/// the game is drawn if nobody gets a line of minisquares.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Ruleset {
+    /// Won minisquares can still be played in until they're full, though they stay with whoever
+    /// won them first.
+    pub play_in_won_minisquares: bool,
+    /// Being sent to a minisquare nobody can play in lets you play in any minisquare that isn't
+    /// full, even ones that have already been won.
+    pub free_move_anywhere: bool,
+    /// If the game ends without a line of minisquares, whoever won more minisquares wins.
+    pub minisquare_tiebreak: bool,
+}
+
+impl Ruleset {
+    pub const STANDARD: Ruleset = Ruleset {
+        play_in_won_minisquares: false,
+        free_move_anywhere: false,
+        minisquare_tiebreak: false,
+    };
+
+    /// Packs the ruleset into a byte, for sending over the network.
+    pub fn to_bits(self) -> u8 {
+        self.play_in_won_minisquares as u8
+            | (self.free_move_anywhere as u8) << 1
+            | (self.minisquare_tiebreak as u8) << 2
+    }
+
+    /// Unpacks a byte from [`Ruleset::to_bits`], or returns `None` if it has bits we don't know.
+    pub fn from_bits(bits: u8) -> Option<Ruleset> {
+        (bits < 1 << 3).then_some(Ruleset {
+            play_in_won_minisquares: bits & 1 != 0,
+            free_move_anywhere: bits & 1 << 1 != 0,
+            minisquare_tiebreak: bits & 1 << 2 != 0,
+        })
+    }
+}
+
+const NAMES: [&str; 3] = ["play-in-won", "free-move-anywhere", "minisquare-tiebreak"];
+
+/// Writes `standard`, or the names of the variations separated by commas, like
+/// `play-in-won,minisquare-tiebreak`.
+impl fmt::Display for Ruleset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if *self == Ruleset::STANDARD {
+            return f.write_str("standard");
+        }
+        let enabled = (0..NAMES.len()).filter(|i| self.to_bits() & 1 << i != 0);
+        let names: Vec<&str> = enabled.map(|i| NAMES[i]).collect();
+        f.write_str(&names.join(","))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownRule(pub String);
+
+impl fmt::Display for UnknownRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown rule {:?}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownRule {}
+
+impl FromStr for Ruleset {
+    type Err = UnknownRule;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "standard" {
+            return Ok(Ruleset::STANDARD);
+        }
+        let mut bits = 0;
+        for name in s.split(',') {
+            let i = NAMES
+                .iter()
+                .position(|known| *known == name.trim())
+                .ok_or_else(|| UnknownRule(name.to_owned()))?;
+            bits |= 1 << i;
+        }
+        Ok(Ruleset::from_bits(bits).unwrap())
+    }
+}
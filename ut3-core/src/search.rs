@@ -0,0 +1,343 @@
+//! A deterministic engine: iterative-deepening negamax with alpha-beta pruning over a hand-tuned
+//! evaluation.
+//!
+//! Scores are from the point of view of the player to move. A won game is [`WIN`] less the number
+//! of moves it takes, so that quicker wins score higher, and a drawn one is 0.
+
+use std::time::{Duration, Instant};
+
+use crate::{
+    bitboard::{FULL, LINES},
+    minisquare_of, BitPosition, GameOutcome, Player,
+};
+
+/// The score of winning right now.
+pub const WIN: i32 = 1_000_000;
+
+/// Deeper than any game can go.
+const MAX_DEPTH: u32 = 81;
+
+/// How much each minisquare is worth, by how many outer lines it's in.
+const WEIGHT: [i32; 9] = [3, 2, 3, 2, 4, 2, 3, 2, 3];
+/// Per unit of `WEIGHT`, for owning a minisquare.
+const MINISQUARE: i32 = 100;
+/// Per unit of `WEIGHT`, for two in a row inside a minisquare with the third tile empty.
+const INNER_TWO: i32 = 12;
+/// For two won minisquares in a row with the third still undecided.
+const OUTER_TWO: i32 = 300;
+/// For being sent somewhere you can win a minisquare straight away, per unit of `WEIGHT`.
+const SENT_TO_WIN: i32 = 25;
+/// For being sent to a closed minisquare, and so getting to play anywhere.
+const FREE_MOVE: i32 = 60;
+
+/// When to stop searching. Whichever limit is reached first ends the search, and with none the
+/// search carries on until the result of the game is certain.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// In moves.
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,
+}
+
+impl Limits {
+    pub fn depth(depth: u32) -> Self {
+        Limits {
+            depth: Some(depth),
+            ..Limits::default()
+        }
+    }
+
+    pub fn nodes(nodes: u64) -> Self {
+        Limits {
+            nodes: Some(nodes),
+            ..Limits::default()
+        }
+    }
+
+    pub fn time(time: Duration) -> Self {
+        Limits {
+            time: Some(time),
+            ..Limits::default()
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchResult {
+    /// `None` if the game is over.
+    pub best_move: Option<usize>,
+    /// See the [module docs](self).
+    pub score: i32,
+    /// The moves the engine expects, starting with `best_move`.
+    pub pv: Vec<usize>,
+    /// How many positions were looked at, including in searches that were cut short.
+    pub nodes: u64,
+    /// The depth of the deepest search that finished.
+    pub depth: u32,
+}
+
+/// Whether `score` is a forced win or loss, rather than an estimate.
+pub fn is_decisive(score: i32) -> bool {
+    score.abs() > WIN - MAX_DEPTH as i32 - 1
+}
+
+/// A score of 400 makes the player to move three times as likely to win as to lose, and this is
+/// 400 / ln 3.
+const SCORE_SCALE: f32 = 364.1;
+
+/// A rough chance of the player to move winning, from 0 to 1, with draws counting as half.
+pub fn win_probability(score: i32) -> f32 {
+    if is_decisive(score) {
+        return if score > 0 { 1. } else { 0. };
+    }
+    1. / (1. + (-score as f32 / SCORE_SCALE).exp())
+}
+
+/// How many lines of `LINES` have exactly two of `ours` and none of `blocked`.
+fn twos(ours: u16, blocked: u16) -> i32 {
+    LINES
+        .iter()
+        .filter(|&&line| line & blocked == 0 && (line & ours).count_ones() == 2)
+        .count() as i32
+}
+
+fn side_score(position: &BitPosition, player: Player) -> i32 {
+    let won = position.won(player);
+    let decided = position.decided();
+    let mut score = OUTER_TWO * twos(won, decided & !won);
+    for (minisquare, weight) in WEIGHT.iter().enumerate() {
+        let bit = 1 << minisquare;
+        if won & bit != 0 {
+            score += MINISQUARE * weight;
+        } else if decided & bit == 0 {
+            let ours = position.tiles_in(player, minisquare);
+            let theirs = position.tiles_in(!player, minisquare);
+            score += INNER_TWO * weight * twos(ours, theirs);
+        }
+    }
+    score
+}
+
+/// Whether `player` can win `minisquare` with their next tile there.
+fn can_win(position: &BitPosition, player: Player, minisquare: usize) -> bool {
+    let ours = position.tiles_in(player, minisquare);
+    let empty = !position.taken(minisquare) & FULL;
+    position.decided() & (1 << minisquare) == 0
+        && LINES
+            .iter()
+            .any(|&line| (line & ours).count_ones() == 2 && line & empty != 0)
+}
+
+/// A guess at how good a position is for the player to move, not counting whether the game is
+/// over.
+pub fn evaluate(position: &BitPosition) -> i32 {
+    let us = position.side_to_move();
+    let mut score = side_score(position, us) - side_score(position, !us);
+    // whoever just moved is blamed for where they sent us
+    score += match position.forced_board() {
+        None => FREE_MOVE,
+        Some(minisquare) if can_win(position, us, minisquare) => SENT_TO_WIN * WEIGHT[minisquare],
+        Some(_) => 0,
+    };
+    score
+}
+
+/// How promising a move looks before searching it, so that the best moves are tried first and
+/// the rest get cut off sooner.
+fn move_order(position: &BitPosition, coord: usize, pv_move: Option<usize>) -> i32 {
+    if Some(coord) == pv_move {
+        return i32::MAX;
+    }
+    let us = position.side_to_move();
+    let mut after = *position;
+    after.make(coord);
+    let mut order = 0;
+    if after.won(us) != position.won(us) {
+        order += MINISQUARE * WEIGHT[minisquare_of(coord)];
+    }
+    match after.forced_board() {
+        None => order -= FREE_MOVE,
+        Some(target) if can_win(&after, !us, target) => order -= SENT_TO_WIN * WEIGHT[target],
+        Some(_) => {}
+    }
+    order
+}
+
+struct Searcher {
+    limits: Limits,
+    start: Instant,
+    nodes: u64,
+    aborted: bool,
+    /// The best line from the last finished iteration, tried first in the next one.
+    pv: Vec<usize>,
+    /// `lines[ply]` is the best line found so far from the position `ply` moves in.
+    lines: Vec<Vec<usize>>,
+}
+
+impl Searcher {
+    fn new(limits: Limits) -> Self {
+        Searcher {
+            limits,
+            start: Instant::now(),
+            nodes: 0,
+            aborted: false,
+            pv: Vec::new(),
+            lines: vec![Vec::new(); MAX_DEPTH as usize + 1],
+        }
+    }
+
+    fn out_of_budget(&self) -> bool {
+        self.limits.nodes.is_some_and(|limit| self.nodes >= limit)
+            || self
+                .limits
+                .time
+                .is_some_and(|limit| self.start.elapsed() >= limit)
+    }
+
+    /// Scores `position` to `depth` moves, leaving the best line from it in `lines[ply]`.
+    /// `on_pv` is whether the moves so far are the start of the last iteration's best line.
+    fn negamax(
+        &mut self,
+        position: &mut BitPosition,
+        depth: u32,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+        on_pv: bool,
+    ) -> i32 {
+        // checking the clock is slow, so only do it now and then
+        let check = self.limits.time.is_none() || self.nodes.is_multiple_of(1024);
+        if check && self.out_of_budget() {
+            self.aborted = true;
+        }
+        if self.aborted {
+            return 0;
+        }
+        self.nodes += 1;
+        self.lines[ply].clear();
+        match position.outcome() {
+            GameOutcome::Ongoing => {}
+            GameOutcome::Win(winner, _) if winner == position.side_to_move() => {
+                return WIN - ply as i32
+            }
+            GameOutcome::Win(..) => return -(WIN - ply as i32),
+            GameOutcome::Draw(_) => return 0,
+        }
+        if depth == 0 {
+            return evaluate(position);
+        }
+
+        let pv_move = self.pv.get(ply).copied().filter(|_| on_pv);
+        let mut moves: Vec<_> = position.legal_moves().iter().map(|&c| c as usize).collect();
+        moves.sort_by_cached_key(|&coord| std::cmp::Reverse(move_order(position, coord, pv_move)));
+
+        let mut best = -WIN - 1;
+        for coord in moves {
+            let unmake = position.make(coord);
+            let on_pv = Some(coord) == pv_move;
+            let score = -self.negamax(position, depth - 1, ply + 1, -beta, -alpha, on_pv);
+            position.unmake(coord, unmake);
+            if self.aborted {
+                return 0;
+            }
+            if score > best {
+                best = score;
+                let (line, rest) = self.lines.split_at_mut(ply + 1);
+                let line = &mut line[ply];
+                line.clear();
+                line.push(coord);
+                line.extend_from_slice(&rest[0]);
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        best
+    }
+}
+
+/// Searches `position` more and more deeply until one of `limits` is reached, returning the best
+/// move found by the deepest search that finished.
+pub fn search(position: &BitPosition, limits: Limits) -> SearchResult {
+    let mut searcher = Searcher::new(limits);
+    let mut result = SearchResult {
+        best_move: None,
+        score: 0,
+        pv: Vec::new(),
+        nodes: 0,
+        depth: 0,
+    };
+    let mut position = *position;
+    if position.whose_turn().is_none() {
+        result.score = searcher.negamax(&mut position, 0, 0, -WIN - 1, WIN + 1, false);
+        result.nodes = searcher.nodes;
+        return result;
+    }
+
+    let max_depth = limits.depth.unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH);
+    for depth in 1..=max_depth {
+        let score = searcher.negamax(&mut position, depth, 0, -WIN - 1, WIN + 1, true);
+        // a search that was cut short could have missed anything, unless it's the only one
+        if searcher.aborted && result.best_move.is_some() {
+            break;
+        }
+        let pv = std::mem::take(&mut searcher.lines[0]);
+        result.best_move = pv.first().copied();
+        result.score = score;
+        result.pv = pv.clone();
+        result.depth = if searcher.aborted { depth - 1 } else { depth };
+        searcher.pv = pv;
+        if searcher.aborted || is_decisive(score) || searcher.out_of_budget() {
+            break;
+        }
+    }
+    // even a depth 1 search can run out of nodes
+    if result.best_move.is_none() {
+        result.best_move = position.legal_moves().first().map(|&coord| coord as usize);
+        result.pv = result.best_move.into_iter().collect();
+    }
+    result.nodes = searcher.nodes;
+    result
+}
+
+/// The score of every legal move in `position`, in the same order as
+/// [`BitPosition::legal_moves`]. Unlike [`search`], every move gets an exact score rather than
+/// just the best one, which makes this slower. Every move is scored at least one move deep even
+/// if that goes over the limits.
+pub fn score_moves(position: &BitPosition, limits: Limits) -> Vec<(usize, i32)> {
+    let mut position = *position;
+    let moves = position.legal_moves();
+    let mut score_all = |searcher: &mut Searcher, depth: u32| {
+        let mut scores = Vec::with_capacity(moves.len());
+        for &coord in moves.iter() {
+            let unmake = position.make(coord as usize);
+            let score = -searcher.negamax(&mut position, depth - 1, 1, -WIN - 1, WIN + 1, false);
+            position.unmake(coord as usize, unmake);
+            if searcher.aborted {
+                return None;
+            }
+            scores.push((coord as usize, score));
+        }
+        Some(scores)
+    };
+
+    // one move deep is only an evaluation of each move, so it's quick enough to always finish
+    let mut scores = score_all(&mut Searcher::new(Limits::default()), 1)
+        .expect("a search without limits can't be cut short");
+    let mut searcher = Searcher::new(limits);
+    let max_depth = limits.depth.unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH);
+    for depth in 2..=max_depth {
+        let settled = scores.iter().all(|&(_, score)| is_decisive(score));
+        if settled || searcher.out_of_budget() {
+            break;
+        }
+        // as with `search`, a depth that was cut short doesn't count
+        match score_all(&mut searcher, depth) {
+            Some(these) => scores = these,
+            None => break,
+        }
+    }
+    scores
+}
@@ -0,0 +1,136 @@
+//! The eight ways to rotate and reflect the board. Each one moves the minisquares around the
+//! outer board and the tiles around inside every minisquare in the same way, so it takes legal
+//! games to legal games and doesn't change who's winning. Opening books, game databases and
+//! transposition tables can store one canonical position for each group of eight.
+
+use crate::{MinisquareState, Player, Position};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Symmetry {
+    Identity,
+    /// A quarter turn clockwise.
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// Left and right swap.
+    FlipHorizontal,
+    /// Top and bottom swap.
+    FlipVertical,
+    /// Reflect in the diagonal from the top left to the bottom right.
+    FlipDiagonal,
+    /// Reflect in the diagonal from the top right to the bottom left.
+    FlipAntiDiagonal,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipHorizontal,
+        Symmetry::FlipVertical,
+        Symmetry::FlipDiagonal,
+        Symmetry::FlipAntiDiagonal,
+    ];
+
+    /// Where the square at `(row, column)` of an `n` by `n` grid ends up.
+    const fn apply_to_grid(self, row: usize, column: usize, n: usize) -> (usize, usize) {
+        let last = n - 1;
+        match self {
+            Symmetry::Identity => (row, column),
+            Symmetry::Rotate90 => (column, last - row),
+            Symmetry::Rotate180 => (last - row, last - column),
+            Symmetry::Rotate270 => (last - column, row),
+            Symmetry::FlipHorizontal => (row, last - column),
+            Symmetry::FlipVertical => (last - row, column),
+            Symmetry::FlipDiagonal => (column, row),
+            Symmetry::FlipAntiDiagonal => (last - column, last - row),
+        }
+    }
+
+    /// Where the tile at `coord` ends up.
+    pub const fn apply(self, coord: usize) -> usize {
+        let (row, column) = self.apply_to_grid(coord / 9, coord % 9, 9);
+        row * 9 + column
+    }
+
+    /// Where the minisquare at `index` ends up. Also works for a tile's position within its
+    /// minisquare.
+    pub const fn apply_to_minisquare(self, index: usize) -> usize {
+        let (row, column) = self.apply_to_grid(index / 3, index % 3, 3);
+        row * 3 + column
+    }
+
+    /// The symmetry that undoes this one.
+    pub const fn inverse(self) -> Symmetry {
+        match self {
+            Symmetry::Rotate90 => Symmetry::Rotate270,
+            Symmetry::Rotate270 => Symmetry::Rotate90,
+            other => other,
+        }
+    }
+
+    pub fn apply_to_history(self, moves: &[usize]) -> Vec<usize> {
+        moves.iter().map(|&coord| self.apply(coord)).collect()
+    }
+}
+
+/// Puts positions in an arbitrary but fixed order, so that the smallest can be picked. Only looks
+/// at what [`Position`]'s `==` does, so equal positions get the same key.
+fn sort_key(position: &Position, symmetry: Symmetry) -> [u8; 91] {
+    let inverse = symmetry.inverse();
+    let player = |player: Option<Player>| match player {
+        None => 0,
+        Some(Player::Cross) => 1,
+        Some(Player::Nought) => 2,
+    };
+    let mut key = [0; 91];
+    for (coord, k) in key[..81].iter_mut().enumerate() {
+        *k = player(position.tile(inverse.apply(coord)));
+    }
+    for (index, k) in key[81..90].iter_mut().enumerate() {
+        *k = match position.minisquare(inverse.apply_to_minisquare(index)) {
+            MinisquareState::Open => 0,
+            MinisquareState::Won(winner) => player(Some(winner)),
+            MinisquareState::Drawn => 3,
+        };
+    }
+    key[90] = match position.forced_board() {
+        Some(index) => symmetry.apply_to_minisquare(index) as u8,
+        None => 9,
+    };
+    key
+}
+
+impl Position {
+    /// The symmetry that takes this position to its canonical form. If the position is symmetric
+    /// itself, more than one would do, and the first in [`Symmetry::ALL`] is picked.
+    pub fn canonical_symmetry(&self) -> Symmetry {
+        Symmetry::ALL
+            .into_iter()
+            .min_by_key(|&symmetry| sort_key(self, symmetry))
+            .unwrap()
+    }
+
+    /// The same position for all eight symmetries of it, along with the moves that led there
+    /// transformed to match.
+    pub fn canonical(&self) -> Position {
+        self.transformed(self.canonical_symmetry())
+    }
+
+    /// A hash that's the same for all eight symmetries of a position.
+    pub fn canonical_zobrist(&self) -> u64 {
+        self.canonical().zobrist()
+    }
+}
+
+/// The symmetry that makes `moves` come first when compared move by move, and the moves
+/// transformed by it. Games that are the same up to symmetry give the same moves.
+pub fn canonical_history(moves: &[usize]) -> (Symmetry, Vec<usize>) {
+    Symmetry::ALL
+        .into_iter()
+        .map(|symmetry| (symmetry, symmetry.apply_to_history(moves)))
+        .min_by(|(_, a), (_, b)| a.cmp(b))
+        .unwrap()
+}
@@ -0,0 +1,53 @@
+//! Random keys for hashing positions. A position's hash is the xor of the keys for everything in
+//! it, so it can be updated a move at a time. The keys are generated at compile time from a fixed
+//! seed, so hashes are the same in every build and on every machine.
+
+use crate::{MinisquareState, Player};
+
+const TILES: usize = 0;
+const MINISQUARES: usize = TILES + 81 * 2;
+const FORCED_BOARDS: usize = MINISQUARES + 9 * 3;
+const NOUGHT_TO_MOVE: usize = FORCED_BOARDS + 9;
+const KEY_COUNT: usize = NOUGHT_TO_MOVE + 1;
+
+static KEYS: [u64; KEY_COUNT] = {
+    // splitmix64
+    let mut keys = [0; KEY_COUNT];
+    let mut state: u64 = 0x7574_3320_7a6f_6272; // "ut3 zobr"
+    let mut i = 0;
+    while i < KEY_COUNT {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    keys
+};
+
+pub(crate) fn tile(coord: usize, player: Player) -> u64 {
+    KEYS[TILES + coord * 2 + player as usize]
+}
+
+/// Open minisquares don't contribute to the hash.
+pub(crate) fn minisquare(index: usize, state: MinisquareState) -> u64 {
+    match state {
+        MinisquareState::Open => 0,
+        MinisquareState::Won(player) => KEYS[MINISQUARES + index * 3 + player as usize],
+        MinisquareState::Drawn => KEYS[MINISQUARES + index * 3 + 2],
+    }
+}
+
+/// Being free to play anywhere doesn't contribute to the hash.
+pub(crate) fn forced_board(board: Option<usize>) -> u64 {
+    board.map_or(0, |board| KEYS[FORCED_BOARDS + board])
+}
+
+/// Cross to move doesn't contribute to the hash.
+pub(crate) fn side_to_move(player: Player) -> u64 {
+    match player {
+        Player::Nought => KEYS[NOUGHT_TO_MOVE],
+        Player::Cross => 0,
+    }
+}
@@ -0,0 +1,55 @@
+//! Checks that `BitPosition` plays by exactly the same rules as `Position`.
+
+mod common;
+
+use common::Rng;
+use ut3_core::{BitPosition, Position, Ruleset};
+
+fn assert_same(position: &Position, bits: &BitPosition) {
+    let legal: Vec<usize> = position.legal_moves().collect();
+    let bit_legal: Vec<usize> = bits.legal_moves().iter().map(|&m| m as usize).collect();
+    assert_eq!(legal, bit_legal, "history {:?}", position.history());
+    for coord in 0..81 {
+        assert_eq!(position.tile(coord), bits.tile(coord));
+        assert_eq!(position.is_legal(coord), bits.is_legal(coord));
+    }
+    for minisquare in 0..9 {
+        assert_eq!(position.minisquare(minisquare), bits.minisquare(minisquare));
+    }
+    assert_eq!(position.whose_turn(), bits.whose_turn());
+    assert_eq!(position.forced_board(), bits.forced_board());
+    assert_eq!(position.outcome(), bits.outcome());
+    assert_eq!(position.zobrist(), bits.zobrist());
+    assert_eq!(BitPosition::from(position), *bits);
+}
+
+#[test]
+fn random_games_match_reference_rules() {
+    let mut rng = Rng(0x5eed_1234_abcd_ef01);
+    for game in 0..512 {
+        // every combination of rules gets the same number of games
+        let rules = Ruleset::from_bits((game % 8) as u8).unwrap();
+        let mut position = Position::with_rules(rules);
+        let mut bits = BitPosition::with_rules(rules);
+        let mut made = Vec::new();
+        assert_same(&position, &bits);
+        while !position.outcome().is_over() {
+            let coord = rng.legal_move(&position).unwrap();
+            let before = bits;
+            let unmake = bits.make(coord);
+            position.try_play(coord).unwrap();
+            assert_same(&position, &bits);
+
+            // taking a move back and playing it again must get us to the same place
+            bits.unmake(coord, unmake);
+            assert_eq!(bits, before);
+            bits.make(coord);
+            made.push((coord, unmake, before));
+        }
+        while let Some((coord, unmake, before)) = made.pop() {
+            bits.unmake(coord, unmake);
+            assert_eq!(bits, before);
+        }
+        assert_eq!(bits, BitPosition::with_rules(rules));
+    }
+}
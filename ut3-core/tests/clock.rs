@@ -0,0 +1,114 @@
+use std::time::{Duration, Instant};
+
+use ut3_core::{
+    clock::{Clock, TimeControl},
+    record::GameRecord,
+    GameOutcome, Player, Position, Termination,
+};
+
+const SECOND: Duration = Duration::from_secs(1);
+
+#[test]
+fn time_controls_round_trip() {
+    for s in ["300+5", "60+0", "30/move"] {
+        assert_eq!(s.parse::<TimeControl>().unwrap().to_string(), s);
+    }
+    for s in ["", "300", "+5", "5+", "0+0", "0/move", "x/move", "300+5+5"] {
+        assert!(s.parse::<TimeControl>().is_err(), "{s:?} parsed");
+    }
+}
+
+#[test]
+fn increment_is_added_after_each_move() {
+    let start = Instant::now();
+    let mut clock = Clock::new("60+2".parse().unwrap());
+    clock.start(Player::Cross, start);
+    assert_eq!(
+        clock.remaining(Player::Cross, start + 10 * SECOND),
+        50 * SECOND
+    );
+    assert_eq!(
+        clock.remaining(Player::Nought, start + 10 * SECOND),
+        60 * SECOND
+    );
+
+    assert_eq!(clock.press(start + 10 * SECOND), 52 * SECOND);
+    assert_eq!(clock.running(), Some(Player::Nought));
+    assert_eq!(
+        clock.remaining(Player::Cross, start + 30 * SECOND),
+        52 * SECOND
+    );
+    assert_eq!(
+        clock.remaining(Player::Nought, start + 30 * SECOND),
+        40 * SECOND
+    );
+    assert_eq!(clock.flagged(start + 30 * SECOND), None);
+    assert_eq!(clock.flagged(start + 70 * SECOND), Some(Player::Nought));
+    assert_eq!(
+        clock.remaining(Player::Nought, start + 70 * SECOND),
+        Duration::ZERO
+    );
+}
+
+#[test]
+fn per_move_time_resets() {
+    let start = Instant::now();
+    let mut clock = Clock::new(TimeControl::PerMove(10 * SECOND));
+    clock.start(Player::Cross, start);
+    assert_eq!(clock.press(start + 4 * SECOND), 10 * SECOND);
+    assert_eq!(clock.flagged(start + 13 * SECOND), None);
+    assert_eq!(clock.flagged(start + 14 * SECOND), Some(Player::Nought));
+}
+
+#[test]
+fn opponent_reports_their_time() {
+    let start = Instant::now();
+    let mut clock = Clock::new("60+0".parse().unwrap());
+    clock.start(Player::Cross, start);
+    clock.press(start + 5 * SECOND);
+    // our view of cross's time after the move is corrected to what they saw
+    clock.set_remaining(Player::Cross, 57 * SECOND, start + 6 * SECOND);
+    assert_eq!(
+        clock.remaining(Player::Cross, start + 6 * SECOND),
+        57 * SECOND
+    );
+    clock.stop(start + 8 * SECOND);
+    assert_eq!(clock.running(), None);
+    assert_eq!(
+        clock.remaining(Player::Nought, start + 100 * SECOND),
+        57 * SECOND
+    );
+}
+
+#[test]
+fn opponent_is_flagged_after_the_grace() {
+    let start = Instant::now();
+    let mut clock = Clock::new("60+0".parse().unwrap());
+    clock.start(Player::Cross, start);
+    clock.press(start + 10 * SECOND);
+    assert_eq!(clock.flagged(start + 70 * SECOND), Some(Player::Nought));
+    assert_eq!(clock.flagged_by(3 * SECOND, start + 72 * SECOND), None);
+    assert_eq!(
+        clock.flagged_by(3 * SECOND, start + 73 * SECOND),
+        Some(Player::Nought)
+    );
+    clock.stop(start + 80 * SECOND);
+    assert_eq!(clock.flagged_by(3 * SECOND, start + 100 * SECOND), None);
+}
+
+#[test]
+fn timeouts_are_recorded() {
+    let mut position = Position::new();
+    position.try_play(40).unwrap();
+    position.time_out(Player::Nought);
+    assert_eq!(
+        position.outcome(),
+        GameOutcome::Win(Player::Cross, Termination::Timeout)
+    );
+    assert!(!position.is_legal(30));
+
+    let text = GameRecord::from_position(&position).to_string();
+    assert!(text.contains("[Termination \"timeout\"]"), "{text}");
+    let record: GameRecord = text.parse().unwrap();
+    assert_eq!(record.replay(), Ok(position));
+}
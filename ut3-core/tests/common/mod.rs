@@ -0,0 +1,41 @@
+// each test file only uses some of these
+#![allow(dead_code)]
+
+use ut3_core::{Position, Ruleset};
+
+/// xorshift64, so that randomised tests do the same thing on every run.
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+
+    /// A random legal move, or `None` if the game is over.
+    pub fn legal_move(&mut self, position: &Position) -> Option<usize> {
+        let count = position.legal_moves().count();
+        (count > 0).then(|| pick_move(position, self.below(count)).unwrap())
+    }
+}
+
+/// The legal move `choice` picks, counting round again past the last one, or `None` if the game
+/// is over.
+pub fn pick_move(position: &Position, choice: usize) -> Option<usize> {
+    let moves: Vec<usize> = position.legal_moves().collect();
+    (!moves.is_empty()).then(|| moves[choice % moves.len()])
+}
+
+/// Random moves from the start until the game ends or `max_moves` have been played.
+pub fn random_game(rng: &mut Rng, rules: Ruleset, max_moves: usize) -> Position {
+    let mut position = Position::with_rules(rules);
+    while position.history().len() < max_moves {
+        let Some(coord) = rng.legal_move(&position) else {
+            break;
+        };
+        position.try_play(coord).unwrap();
+    }
+    position
+}
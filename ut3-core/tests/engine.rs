@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use ut3_core::{
+    engine::{Command, Engine, Info, ProtocolError, Response},
+    search::{Limits, WIN},
+    Position, Ruleset,
+};
+
+#[test]
+fn commands_round_trip() {
+    let commands = [
+        "ut3",
+        "isready",
+        "newgame",
+        "position startpos",
+        "position startpos moves E5 E4 D2",
+        "position fen 9/9/9/9/4x4/9/9/9/9 ......... o C rules play-in-won moves E4",
+        "position fen 9/9/9/9/4x4/9/9/9/9 ......... o C",
+        "go",
+        "go movetime 1500 depth 6 nodes 10000",
+        "quit",
+    ];
+    for text in commands {
+        let command: Command = text.parse().unwrap();
+        assert_eq!(command.to_string(), text);
+    }
+}
+
+#[test]
+fn responses_round_trip() {
+    let responses = [
+        "id name ut3-engine",
+        "id author Someone Else",
+        "ut3ok",
+        "readyok",
+        "info depth 7 score -40 nodes 282137 time 120 pv D2 A4 B3",
+        "info score win 3",
+        "info score loss 4 pv A1",
+        "info",
+        "bestmove E5",
+        "bestmove none",
+    ];
+    for text in responses {
+        let response: Response = text.parse().unwrap();
+        assert_eq!(response.to_string(), text);
+    }
+    let Ok(Response::Info(info)) = "info score win 3".parse() else {
+        panic!()
+    };
+    assert_eq!(info.score, Some(WIN - 3));
+}
+
+#[test]
+fn relative_moves_and_unknown_fields() {
+    let command: Command = "position startpos moves C/c c/n".parse().unwrap();
+    assert_eq!(
+        command,
+        Command::Position {
+            start: None,
+            rules: Ruleset::STANDARD,
+            moves: vec![40, 31],
+        }
+    );
+    let response: Response = "info depth 3 seldepth 9 nodes 50".parse().unwrap();
+    assert_eq!(
+        response,
+        Response::Info(Info {
+            depth: Some(3),
+            nodes: Some(50),
+            ..Info::default()
+        })
+    );
+}
+
+#[test]
+fn bad_lines() {
+    assert!(matches!(
+        "hello".parse::<Command>(),
+        Err(ProtocolError::Unknown(_))
+    ));
+    assert!(matches!(
+        "go movetime soon".parse::<Command>(),
+        Err(ProtocolError::BadValue {
+            name: "movetime",
+            ..
+        })
+    ));
+    assert!(matches!(
+        "position startpos moves Z9".parse::<Command>(),
+        Err(ProtocolError::BadMove(_))
+    ));
+}
+
+#[test]
+fn position_of_a_game() {
+    let mut position = Position::new();
+    for coord in [40, 31, 13] {
+        position.try_play(coord).unwrap();
+    }
+    let command = Command::position(&position);
+    assert_eq!(command.to_string(), "position startpos moves E5 E4 E2");
+    let mut engine = Engine::new("test");
+    engine.handle(&command).unwrap();
+    assert_eq!(engine.position(), Some(&position));
+}
+
+#[test]
+fn conversation() {
+    let mut engine = Engine::new("test");
+    let handshake = engine.handle(&Command::Ut3).unwrap();
+    assert_eq!(handshake.last(), Some(&Response::Ut3Ok));
+    assert_eq!(
+        engine.handle(&Command::IsReady).unwrap(),
+        [Response::ReadyOk]
+    );
+
+    let command = "position startpos moves E5 E4".parse().unwrap();
+    assert!(engine.handle(&command).unwrap().is_empty());
+    let responses = engine
+        .handle(&Command::Go(Limits {
+            depth: Some(4),
+            time: Some(Duration::from_secs(10)),
+            ..Limits::default()
+        }))
+        .unwrap();
+    let [Response::Info(info), Response::BestMove(Some(coord))] = &responses[..] else {
+        panic!("{responses:?}");
+    };
+    assert_eq!(info.depth, Some(4));
+    assert!(engine.position().unwrap().is_legal(*coord));
+
+    let illegal = "position startpos moves E5 E5".parse().unwrap();
+    assert!(matches!(
+        engine.handle(&illegal),
+        Err(ProtocolError::IllegalMove { ply: 2, .. })
+    ));
+}
+
+#[test]
+fn bad_position_is_not_searched() {
+    let mut engine = Engine::new("test");
+    let go = Command::Go(Limits::depth(2));
+    for bad in [
+        "position startpos moves E5 E5",
+        "position startpos moves Z9",
+        "position fen nonsense",
+    ] {
+        engine.handle_line("position startpos moves E5").unwrap();
+        assert!(engine.handle_line(bad).is_err());
+        assert_eq!(engine.position(), None, "{bad}");
+        assert_eq!(engine.handle(&go).unwrap(), [Response::BestMove(None)]);
+    }
+    engine.handle_line("position startpos").unwrap();
+    assert!(matches!(
+        engine.handle(&go).unwrap()[..],
+        [Response::Info(_), Response::BestMove(Some(_))]
+    ));
+}
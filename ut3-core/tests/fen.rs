@@ -0,0 +1,79 @@
+mod common;
+
+use common::Rng;
+use ut3_core::{fen::ParsePositionError, Position, Ruleset};
+
+#[test]
+fn start_position() {
+    assert_eq!(Position::new().to_string(), ut3_core::fen::STARTPOS);
+    assert_eq!(ut3_core::fen::STARTPOS.parse(), Ok(Position::new()));
+}
+
+#[test]
+fn centre_opening() {
+    let mut position = Position::new();
+    position.try_play(40).unwrap();
+    assert_eq!(position.to_string(), "9/9/9/9/4x4/9/9/9/9 ......... o C");
+}
+
+#[test]
+fn round_trip_through_random_games() {
+    let mut rng = Rng(0x0fe7_5eed);
+    for _ in 0..100 {
+        let mut position = Position::new();
+        while !position.outcome().is_over() {
+            let coord = rng.legal_move(&position).unwrap();
+            position.try_play(coord).unwrap();
+            let parsed: Position = position.to_string().parse().unwrap();
+            assert_eq!(parsed, position);
+            assert_eq!(parsed.zobrist(), position.zobrist());
+            assert_eq!(parsed.outcome(), position.outcome());
+            assert!(parsed.legal_moves().eq(position.legal_moves()));
+        }
+    }
+}
+
+#[test]
+fn rules_are_not_part_of_the_string() {
+    let rules = Ruleset {
+        play_in_won_minisquares: true,
+        ..Ruleset::STANDARD
+    };
+    let position = Position::from_fen(ut3_core::fen::STARTPOS, rules).unwrap();
+    assert_eq!(position.rules(), rules);
+}
+
+#[test]
+fn rejects_impossible_positions() {
+    let inconsistent = |s: &str| {
+        matches!(
+            s.parse::<Position>(),
+            Err(ParsePositionError::Inconsistent(_))
+        )
+    };
+    // nought to move without cross having moved
+    assert!(inconsistent("9/9/9/9/9/9/9/9/9 ......... o -"));
+    // a line that hasn't been marked as won
+    assert!(inconsistent("xxx6/oo7/9/9/9/9/9/9/9 ......... o -"));
+    // sent to a won minisquare
+    assert!(inconsistent("xxx6/oo7/9/9/9/9/9/9/9 x........ o NW"));
+}
+
+#[test]
+fn rejects_malformed_strings() {
+    for s in [
+        "",
+        "9/9/9/9/9/9/9/9/9 ......... x",
+        "9/9/9/9/9/9/9/9 ......... x -",
+        "9/9/9/9/9/9/9/9/8 ......... x -",
+        "9/9/9/9/9/9/9/9/91 ......... x -",
+        "9/9/9/9/9/9/9/9/45 ......... x -",
+        "9/9/9/9/9/9/9/9/x35 ......... x -",
+        "9/9/9/9/9/9/9/9/09 ......... x -",
+        "9/9/9/9/9/9/9/9/9 ........ x -",
+        "9/9/9/9/9/9/9/9/9 ......... z -",
+        "9/9/9/9/9/9/9/9/9 ......... x Q",
+    ] {
+        assert!(s.parse::<Position>().is_err(), "{s:?} parsed");
+    }
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use common::Rng;
+use ut3_core::{
+    mcts::{best_move, Budget},
+    BitPosition, GameOutcome, Termination,
+};
+
+/// Random positions where the player to move can win the whole game straight away, with the
+/// moves that do it.
+fn winning_positions(rng: &mut Rng) -> impl Iterator<Item = (BitPosition, Vec<usize>)> + '_ {
+    std::iter::from_fn(move || loop {
+        let mut position = BitPosition::new();
+        while position.whose_turn().is_some() {
+            let moves = position.legal_moves();
+            let winning: Vec<_> = moves
+                .iter()
+                .map(|&coord| coord as usize)
+                .filter(|&coord| {
+                    let mut after = position;
+                    after.make(coord);
+                    matches!(after.outcome(), GameOutcome::Win(_, Termination::OuterLine))
+                })
+                .collect();
+            if !winning.is_empty() {
+                return Some((position, winning));
+            }
+            position.make(moves[rng.below(moves.len())] as usize);
+        }
+    })
+}
+
+#[test]
+fn takes_a_win_in_one() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for (position, winning) in winning_positions(&mut rng).take(20) {
+        let coord = best_move(&position, Budget::Playouts(2000), 1).unwrap();
+        assert!(winning.contains(&coord), "{coord} isn't one of {winning:?}");
+    }
+}
+
+#[test]
+fn plays_legal_moves_to_the_end() {
+    let mut position = BitPosition::new();
+    let mut seed = 0;
+    while position.whose_turn().is_some() {
+        let coord = best_move(&position, Budget::Playouts(50), seed).unwrap();
+        assert!(position.is_legal(coord));
+        position.make(coord);
+        seed += 1;
+    }
+    assert_eq!(best_move(&position, Budget::Playouts(50), seed), None);
+}
+
+#[test]
+fn same_seed_same_move() {
+    let mut position = BitPosition::new();
+    position.make(40);
+    let first = best_move(&position, Budget::Playouts(500), 7);
+    assert_eq!(best_move(&position, Budget::Playouts(500), 7), first);
+}
+
+#[test]
+fn moves_without_any_playouts() {
+    let position = BitPosition::new();
+    let coord = best_move(&position, Budget::Playouts(0), 0).unwrap();
+    assert!(position.is_legal(coord));
+}
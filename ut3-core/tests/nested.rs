@@ -0,0 +1,155 @@
+mod common;
+
+use common::Rng;
+use ut3_core::{
+    nested::{BadGeometry, Geometry, NestedPosition},
+    GameOutcome, MinisquareState, Player, Position, Ruleset,
+};
+
+#[test]
+fn paths_round_trip() {
+    for (size, depth) in [(3, 1), (3, 2), (4, 2), (2, 3), (3, 3)] {
+        let geometry = Geometry {
+            size,
+            depth,
+            win_length: size,
+        };
+        for coord in 0..geometry.tile_count() {
+            let path = geometry.path(coord);
+            assert_eq!(path.len(), depth);
+            assert_eq!(geometry.coord_of_path(&path), coord);
+        }
+    }
+    // the standard board's paths are (minisquare, target)
+    assert_eq!(Geometry::STANDARD.path(40), [4, 4]);
+    assert_eq!(Geometry::STANDARD.path(30), [4, 0]);
+    assert_eq!(Geometry::STANDARD.path(8), [2, 2]);
+}
+
+#[test]
+fn plays_the_same_as_position_on_the_standard_board() {
+    let mut rng = Rng(0x0e57_ed00);
+    for game in 0..256 {
+        let rules = Ruleset::from_bits((game % 8) as u8).unwrap();
+        let mut position = Position::with_rules(rules);
+        let mut nested = NestedPosition::new(Geometry::STANDARD, rules).unwrap();
+        loop {
+            let moves: Vec<usize> = position.legal_moves().collect();
+            assert_eq!(nested.legal_moves(), moves, "{position}");
+            assert_eq!(nested.outcome(), position.outcome());
+            for minisquare in 0..9 {
+                assert_eq!(nested.board(&[minisquare]), position.minisquare(minisquare));
+            }
+            let Some(coord) = rng.legal_move(&position) else {
+                break;
+            };
+            position.try_play(coord).unwrap();
+            nested.try_play(coord).unwrap();
+        }
+        while nested.undo().is_some() {
+            position.undo();
+            assert!(nested
+                .legal_moves()
+                .iter()
+                .copied()
+                .eq(position.legal_moves()));
+        }
+    }
+}
+
+fn count_games(position: &mut NestedPosition, results: &mut [u64; 3]) {
+    match position.outcome() {
+        GameOutcome::Ongoing => {
+            for coord in position.legal_moves() {
+                position.try_play(coord).unwrap();
+                count_games(position, results);
+                position.undo();
+            }
+        }
+        GameOutcome::Win(Player::Cross, _) => results[0] += 1,
+        GameOutcome::Win(Player::Nought, _) => results[1] += 1,
+        GameOutcome::Draw(_) => results[2] += 1,
+    }
+}
+
+#[test]
+fn one_level_is_plain_tic_tac_toe() {
+    let geometry = Geometry {
+        size: 3,
+        depth: 1,
+        win_length: 3,
+    };
+    let mut position = NestedPosition::new(geometry, Ruleset::STANDARD).unwrap();
+    let mut results = [0; 3];
+    count_games(&mut position, &mut results);
+    assert_eq!(results, [131184, 77904, 46080]);
+}
+
+#[test]
+fn three_levels_send_to_the_next_board_down() {
+    let geometry = Geometry {
+        size: 3,
+        depth: 3,
+        win_length: 3,
+    };
+    let mut position = NestedPosition::new(geometry, Ruleset::STANDARD).unwrap();
+    assert_eq!(position.legal_moves().len(), 729);
+    // east medium board, centre small board, north-west tile
+    let coord = geometry.coord_of_path(&[5, 4, 0]);
+    position.try_play(coord).unwrap();
+    assert_eq!(position.forced_board(), [4, 0]);
+    let replies = position.legal_moves();
+    assert_eq!(replies.len(), 9);
+    assert!(replies
+        .iter()
+        .all(|&reply| geometry.path(reply).starts_with(&[4, 0])));
+
+    let mut replies_to_every_opening = 0;
+    for coord in 0..geometry.tile_count() {
+        let mut position = NestedPosition::new(geometry, Ruleset::STANDARD).unwrap();
+        position.try_play(coord).unwrap();
+        replies_to_every_opening += position.legal_moves().len();
+    }
+    assert_eq!(replies_to_every_opening, 720 * 9 + 9 * 8);
+}
+
+#[test]
+fn shorter_lines_on_bigger_boards() {
+    let geometry = Geometry {
+        size: 4,
+        depth: 2,
+        win_length: 3,
+    };
+    // cross takes three of the four tiles along the top of a board
+    let mut position = NestedPosition::new(geometry, Ruleset::STANDARD).unwrap();
+    for path in [[5, 0], [0, 5], [5, 1], [1, 5], [5, 2]] {
+        position.try_play(geometry.coord_of_path(&path)).unwrap();
+    }
+    assert_eq!(position.board(&[5]), MinisquareState::Won(Player::Cross));
+    assert_eq!(position.outcome(), GameOutcome::Ongoing);
+}
+
+#[test]
+fn rejects_bad_geometry() {
+    let bad = |size, depth, win_length| {
+        NestedPosition::new(
+            Geometry {
+                size,
+                depth,
+                win_length,
+            },
+            Ruleset::STANDARD,
+        )
+        .err()
+    };
+    assert_eq!(bad(1, 2, 1), Some(BadGeometry::Size(1)));
+    assert_eq!(bad(3, 0, 3), Some(BadGeometry::Depth(0)));
+    assert_eq!(bad(3, 4, 3), Some(BadGeometry::Depth(4)));
+    assert_eq!(bad(3, 2, 4), Some(BadGeometry::WinLength(4)));
+    assert_eq!(bad(4, 3, 3), Some(BadGeometry::Side(64)));
+    assert_eq!(bad(5, 3, 3), Some(BadGeometry::Side(125)));
+    assert_eq!(bad(3, 3, 3), None);
+    assert_eq!(bad(4, 2, 3), None);
+    assert_eq!(Geometry::max_depth(3), 3);
+    assert_eq!(Geometry::max_depth(5), 2);
+}
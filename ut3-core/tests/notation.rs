@@ -0,0 +1,31 @@
+use ut3_core::notation::{parse_move, Global, Relative};
+
+#[test]
+fn round_trip() {
+    for coord in 0..81 {
+        assert_eq!(parse_move(&Global(coord).to_string()), Ok(coord));
+        assert_eq!(parse_move(&Relative(coord).to_string()), Ok(coord));
+    }
+}
+
+#[test]
+fn known_moves() {
+    assert_eq!(Global(0).to_string(), "A1");
+    assert_eq!(Global(40).to_string(), "E5");
+    assert_eq!(Global(80).to_string(), "I9");
+    assert_eq!(Relative(0).to_string(), "NW/nw");
+    assert_eq!(Relative(10).to_string(), "NW/c");
+    assert_eq!(Relative(40).to_string(), "C/c");
+    assert_eq!(Relative(44).to_string(), "E/e");
+    assert_eq!(parse_move("e5"), Ok(40));
+    assert_eq!(parse_move("se/NW"), Ok(60));
+}
+
+#[test]
+fn rejects_garbage() {
+    for s in [
+        "", "E", "5E", "J1", "A0", "A10", "NW", "NW/", "X/c", "E5/c", "E+5",
+    ] {
+        assert!(parse_move(s).is_err(), "{s:?} parsed");
+    }
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::Rng;
+use ut3_core::{
+    perft::{divide, divide_reference, perft, perft_reference},
+    BitPosition, Position, Ruleset,
+};
+
+#[test]
+fn start_position() {
+    let expected = [1, 81, 720, 6336, 55080, 473256, 4020960];
+    for (depth, &nodes) in expected.iter().enumerate() {
+        assert_eq!(perft(&mut BitPosition::new(), depth as u32), nodes);
+    }
+}
+
+#[test]
+fn reference_agrees_from_the_start() {
+    for bits in 0..8 {
+        let rules = Ruleset::from_bits(bits).unwrap();
+        assert_eq!(
+            perft(&mut BitPosition::with_rules(rules), 4),
+            perft_reference(&mut Position::with_rules(rules), 4),
+            "{rules}"
+        );
+    }
+}
+
+#[test]
+fn known_positions() {
+    let cases = [
+        ("9/9/9/9/4x4/9/9/9/9 ......... o C", 2, 72),
+        ("9/9/9/9/4x4/9/9/9/9 ......... o C", 3, 624),
+    ];
+    for (fen, depth, nodes) in cases {
+        let position: Position = fen.parse().unwrap();
+        assert_eq!(
+            perft(&mut BitPosition::from(&position), depth),
+            nodes,
+            "{fen}"
+        );
+    }
+}
+
+/// Mid- and late-game positions are where the rules about closed minisquares kick in, so divide
+/// from positions along random games, under every ruleset.
+#[test]
+fn reference_agrees_during_random_games() {
+    let mut rng = Rng(0x9e7f_7000);
+    for game in 0..48 {
+        let rules = Ruleset::from_bits((game % 8) as u8).unwrap();
+        let mut position = Position::with_rules(rules);
+        while !position.outcome().is_over() {
+            if position.history().len() % 7 == 3 {
+                let mut bits = BitPosition::from(&position);
+                assert_eq!(
+                    divide(&mut bits, 3),
+                    divide_reference(&mut position.clone(), 3),
+                    "{position} under {rules}"
+                );
+            }
+            let coord = rng.legal_move(&position).unwrap();
+            position.try_play(coord).unwrap();
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! Invariants that should hold in every position of every game, checked by playing random legal
+//! games and comparing against the rules written out the slow, obvious way.
+
+mod common;
+
+use common::pick_move;
+use proptest::prelude::*;
+use ut3_core::{
+    coord_of, minisquare_of, target_of, GameOutcome, MinisquareState, Player, Position, Ruleset,
+    Termination,
+};
+
+const LINES: [[usize; 3]; 8] = [
+    [0, 1, 2],
+    [3, 4, 5],
+    [6, 7, 8],
+    [0, 3, 6],
+    [1, 4, 7],
+    [2, 5, 8],
+    [0, 4, 8],
+    [2, 4, 6],
+];
+
+fn has_line(owned: impl Fn(usize) -> bool) -> bool {
+    LINES.iter().any(|line| line.iter().all(|&i| owned(i)))
+}
+
+fn has_line_in(position: &Position, minisquare: usize, player: Player) -> bool {
+    has_line(|cell| position.tile(coord_of(minisquare, cell)) == Some(player))
+}
+
+fn is_full(position: &Position, minisquare: usize) -> bool {
+    (0..9).all(|cell| position.tile(coord_of(minisquare, cell)).is_some())
+}
+
+/// Whether a minisquare can still be sent to, from the tiles and rules alone.
+fn is_open(position: &Position, minisquare: usize) -> bool {
+    if position.rules().play_in_won_minisquares {
+        !is_full(position, minisquare)
+    } else {
+        position.minisquare(minisquare) == MinisquareState::Open
+    }
+}
+
+/// Whether `coord` could be played if the game weren't over, from the last move and the rules.
+fn could_play(position: &Position, coord: usize) -> bool {
+    if position.tile(coord).is_some() {
+        return false;
+    }
+    let minisquare = minisquare_of(coord);
+    let sent_to = position.history().last().map(|&last| target_of(last));
+    match sent_to {
+        Some(target) if is_open(position, target) => minisquare == target,
+        _ if position.rules().free_move_anywhere => true,
+        _ => is_open(position, minisquare),
+    }
+}
+
+fn check_invariants(position: &Position) -> Result<(), TestCaseError> {
+    for minisquare in 0..9 {
+        let crosses = has_line_in(position, minisquare, Player::Cross);
+        let noughts = has_line_in(position, minisquare, Player::Nought);
+        match position.minisquare(minisquare) {
+            MinisquareState::Won(Player::Cross) => prop_assert!(crosses),
+            MinisquareState::Won(Player::Nought) => prop_assert!(noughts),
+            MinisquareState::Drawn => {
+                prop_assert!(is_full(position, minisquare) && !crosses && !noughts)
+            }
+            MinisquareState::Open => {
+                prop_assert!(!is_full(position, minisquare) && !crosses && !noughts)
+            }
+        }
+    }
+
+    let outer_line = |player| has_line(|i| position.minisquare(i) == MinisquareState::Won(player));
+    let any_moves = (0..81).any(|coord| could_play(position, coord));
+    match position.outcome() {
+        GameOutcome::Ongoing => {
+            prop_assert!(!outer_line(Player::Cross) && !outer_line(Player::Nought));
+            prop_assert!(any_moves);
+        }
+        GameOutcome::Win(winner, Termination::OuterLine) => {
+            prop_assert!(outer_line(winner));
+            prop_assert_eq!(position.side_to_move(), !winner);
+        }
+        GameOutcome::Win(_, _) | GameOutcome::Draw(_) => {
+            prop_assert!(!outer_line(Player::Cross) && !outer_line(Player::Nought));
+            prop_assert!(!any_moves);
+        }
+    }
+
+    for coord in 0..81 {
+        let expected = !position.outcome().is_over() && could_play(position, coord);
+        prop_assert_eq!(
+            position.is_legal(coord),
+            expected,
+            "{} at {}",
+            position,
+            coord
+        );
+    }
+    Ok(())
+}
+
+/// A ruleset, and choices that pick which legal move to play at each turn.
+fn game() -> impl Strategy<Value = (Ruleset, Vec<usize>)> {
+    (
+        (0u8..8).prop_map(|bits| Ruleset::from_bits(bits).unwrap()),
+        prop::collection::vec(any::<usize>(), 0..81),
+    )
+}
+
+fn play(rules: Ruleset, choices: &[usize]) -> Position {
+    let mut position = Position::with_rules(rules);
+    for &choice in choices {
+        let Some(coord) = pick_move(&position, choice) else {
+            break;
+        };
+        position.try_play(coord).unwrap();
+    }
+    position
+}
+
+proptest! {
+    #[test]
+    fn invariants_hold_throughout((rules, choices) in game()) {
+        let mut position = Position::with_rules(rules);
+        check_invariants(&position)?;
+        for choice in choices {
+            let Some(coord) = pick_move(&position, choice) else {
+                break;
+            };
+            position.try_play(coord).unwrap();
+            check_invariants(&position)?;
+        }
+    }
+
+    #[test]
+    fn undo_restores_the_exact_state((rules, choices) in game(), next in any::<usize>()) {
+        let before = play(rules, &choices);
+        let coord = pick_move(&before, next);
+        prop_assume!(coord.is_some());
+        let coord = coord.unwrap();
+
+        let mut position = before.clone();
+        let effects = position.try_play(coord).unwrap();
+        let after = position.clone();
+        prop_assert_eq!(position.undo(), Some(coord));
+        prop_assert_eq!(&position, &before);
+        prop_assert_eq!(position.history(), before.history());
+        prop_assert_eq!(position.zobrist(), before.zobrist());
+        prop_assert_eq!(position.to_string(), before.to_string());
+        prop_assert!(position.legal_moves().eq(before.legal_moves()));
+
+        prop_assert_eq!(position.redo(), Some(effects));
+        prop_assert_eq!(&position, &after);
+        prop_assert_eq!(position.history(), after.history());
+    }
+
+    #[test]
+    fn illegal_moves_change_nothing((rules, choices) in game(), coord in 0usize..100) {
+        let before = play(rules, &choices);
+        let mut position = before.clone();
+        if position.try_play(coord).is_err() {
+            prop_assert!(!before.is_legal(coord));
+            prop_assert_eq!(&position, &before);
+            prop_assert_eq!(position.history(), before.history());
+        } else {
+            prop_assert!(before.is_legal(coord));
+        }
+    }
+}
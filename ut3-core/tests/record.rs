@@ -0,0 +1,81 @@
+mod common;
+
+use common::{random_game, Rng};
+use ut3_core::{
+    record::{GameRecord, RecordError},
+    GameOutcome, IllegalMove, Position, Ruleset,
+};
+
+#[test]
+fn round_trip_through_random_games() {
+    let mut rng = Rng(0x2ec0_2d5e);
+    for game in 0..64 {
+        let rules = Ruleset::from_bits((game % 8) as u8).unwrap();
+        let mut record = GameRecord::from_position(&random_game(&mut rng, rules, 81));
+        record.cross = Some("Alice \"the ace\"".to_owned());
+        record.date = Some("2026.10.18".to_owned());
+        record
+            .other_headers
+            .push(("Event".to_owned(), "Test".to_owned()));
+        let text = record.to_string();
+        assert_eq!(text.parse(), Ok(record), "{text}");
+    }
+}
+
+#[test]
+fn unfinished_game_from_a_position_string() {
+    let mut position: Position = "9/9/9/9/4x4/9/9/9/9 ......... o C".parse().unwrap();
+    position.try_play(30).unwrap();
+    let record = GameRecord::from_position(&position);
+    assert_eq!(record.outcome, GameOutcome::Ongoing);
+    let text = record.to_string();
+    assert!(text.contains("[Position \"9/9/9/9/4x4/9/9/9/9 ......... o C\"]"));
+    assert!(text.contains("[Result \"*\"]"));
+    assert!(text.ends_with("\n1... D4 *\n"), "{text}");
+    let parsed: GameRecord = text.parse().unwrap();
+    assert_eq!(parsed.replay(), Ok(position));
+}
+
+#[test]
+fn accepts_relative_notation_and_no_headers() {
+    let record: GameRecord = "1. C/c C/n 2. N/c".parse().unwrap();
+    assert_eq!(record.moves, [40, 31, 13]);
+    assert_eq!(record.rules, Ruleset::STANDARD);
+}
+
+#[test]
+fn reports_the_first_illegal_move() {
+    // the second move has to be in the centre minisquare
+    assert_eq!(
+        "1. E5 A1 2. B2".parse::<GameRecord>(),
+        Err(RecordError::IllegalMove {
+            ply: 2,
+            text: "A1".to_owned(),
+            error: IllegalMove::WrongBoard,
+        })
+    );
+    assert!(matches!(
+        "1. E5 E5".parse::<GameRecord>(),
+        Err(RecordError::IllegalMove { ply: 2, .. })
+    ));
+    assert!(matches!(
+        "1. E5 Z9".parse::<GameRecord>(),
+        Err(RecordError::BadMove { ply: 2, .. })
+    ));
+}
+
+#[test]
+fn rejects_bad_headers_and_results() {
+    assert!(matches!(
+        "[Cross Alice]\n\n1. E5 *".parse::<GameRecord>(),
+        Err(RecordError::BadHeader { line: 1, .. })
+    ));
+    assert!(matches!(
+        "[Rules \"no-diagonals\"]\n\n*".parse::<GameRecord>(),
+        Err(RecordError::BadHeaderValue { .. })
+    ));
+    assert!(matches!(
+        "[Result \"1-0\"]\n[Termination \"outer line\"]\n\n1. E5 1-0".parse::<GameRecord>(),
+        Err(RecordError::WrongResult { .. })
+    ));
+}
@@ -0,0 +1,180 @@
+mod common;
+
+use common::Rng;
+use ut3_core::{
+    search::{evaluate, score_moves, search, win_probability, Limits, WIN},
+    BitPosition, GameOutcome, Termination,
+};
+
+fn random_position(rng: &mut Rng, moves: usize) -> BitPosition {
+    let mut position = BitPosition::new();
+    for _ in 0..moves {
+        let legal = position.legal_moves();
+        if legal.is_empty() {
+            break;
+        }
+        position.make(legal[rng.below(legal.len())] as usize);
+    }
+    position
+}
+
+/// Negamax without any pruning, scored the same way as the engine.
+fn minimax(position: &mut BitPosition, depth: u32, ply: i32) -> i32 {
+    match position.outcome() {
+        GameOutcome::Ongoing => {}
+        GameOutcome::Win(winner, _) if winner == position.side_to_move() => return WIN - ply,
+        GameOutcome::Win(..) => return -(WIN - ply),
+        GameOutcome::Draw(_) => return 0,
+    }
+    if depth == 0 {
+        return evaluate(position);
+    }
+    let mut best = i32::MIN;
+    for &coord in position.legal_moves().iter() {
+        let unmake = position.make(coord as usize);
+        best = best.max(-minimax(position, depth - 1, ply + 1));
+        position.unmake(coord as usize, unmake);
+    }
+    best
+}
+
+#[test]
+fn pruning_doesnt_change_the_score() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for i in 0..40 {
+        let mut position = random_position(&mut rng, 10 + i);
+        if position.whose_turn().is_none() {
+            continue;
+        }
+        for depth in 1..=3 {
+            let result = search(&position, Limits::depth(depth));
+            assert_eq!(
+                result.score,
+                minimax(&mut position, depth, 0),
+                "depth {depth}"
+            );
+        }
+    }
+}
+
+#[test]
+fn pv_is_legal_and_starts_with_the_best_move() {
+    let mut rng = Rng(0x1234_5678_9abc_def1);
+    for _ in 0..20 {
+        let position = random_position(&mut rng, 12);
+        if position.whose_turn().is_none() {
+            continue;
+        }
+        let result = search(&position, Limits::depth(4));
+        assert_eq!(result.pv.first().copied(), result.best_move);
+        let mut after = position;
+        for &coord in &result.pv {
+            assert!(after.is_legal(coord), "{:?}", result.pv);
+            after.make(coord);
+        }
+    }
+}
+
+#[test]
+fn takes_a_win_in_one() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    let mut found = 0;
+    while found < 10 {
+        let moves = 30 + rng.below(30);
+        let position = random_position(&mut rng, moves);
+        if position.whose_turn().is_none() || minimax(&mut position.clone(), 1, 0) != WIN - 1 {
+            continue;
+        }
+        let result = search(&position, Limits::depth(5));
+        assert_eq!(result.score, WIN - 1);
+        assert_eq!(result.depth, 1, "a forced win should end the search");
+        let mut after = position;
+        after.make(result.best_move.unwrap());
+        assert_eq!(after.outcome().winner(), Some(position.side_to_move()));
+        found += 1;
+    }
+}
+
+#[test]
+fn game_over() {
+    let mut rng = Rng(42);
+    let position = loop {
+        let position = random_position(&mut rng, 81);
+        if let GameOutcome::Win(_, Termination::OuterLine) = position.outcome() {
+            break position;
+        }
+    };
+    let result = search(&position, Limits::depth(3));
+    assert_eq!(result.best_move, None);
+    assert_eq!(result.score, -WIN);
+    assert!(result.pv.is_empty());
+}
+
+#[test]
+fn stops_at_the_node_limit() {
+    let result = search(&BitPosition::new(), Limits::nodes(5000));
+    assert!(result.nodes <= 5000, "{}", result.nodes);
+    assert!(BitPosition::new().is_legal(result.best_move.unwrap()));
+    let tiny = search(&BitPosition::new(), Limits::nodes(1));
+    assert!(tiny.best_move.is_some());
+}
+
+#[test]
+fn is_deterministic() {
+    let position = random_position(&mut Rng(7), 8);
+    assert_eq!(
+        search(&position, Limits::depth(5)),
+        search(&position, Limits::depth(5))
+    );
+}
+
+#[test]
+fn scoring_every_move_agrees_with_the_best() {
+    let mut rng = Rng(0xdead_beef_cafe_f00d);
+    for i in 0..20 {
+        let mut position = random_position(&mut rng, 5 + 2 * i);
+        if position.whose_turn().is_none() {
+            continue;
+        }
+        let scores = score_moves(&position, Limits::depth(3));
+        let moves: Vec<_> = position.legal_moves().iter().map(|&c| c as usize).collect();
+        assert_eq!(
+            scores.iter().map(|&(coord, _)| coord).collect::<Vec<_>>(),
+            moves
+        );
+        for &(coord, score) in &scores {
+            let unmake = position.make(coord);
+            assert_eq!(score, -minimax(&mut position, 2, 1));
+            position.unmake(coord, unmake);
+        }
+        let best = scores.iter().map(|&(_, score)| score).max();
+        assert_eq!(best, Some(search(&position, Limits::depth(3)).score));
+    }
+}
+
+#[test]
+fn every_move_is_scored_however_small_the_budget() {
+    let mut rng = Rng(0x0bad_5eed);
+    for i in 0..10 {
+        let mut position = random_position(&mut rng, 3 * i);
+        if position.whose_turn().is_none() {
+            continue;
+        }
+        let scores = score_moves(&position, Limits::nodes(1));
+        assert_eq!(scores.len(), position.legal_moves().len());
+        for &(coord, score) in &scores {
+            let unmake = position.make(coord);
+            assert_eq!(score, -minimax(&mut position, 0, 1));
+            position.unmake(coord, unmake);
+        }
+    }
+}
+
+#[test]
+fn win_probabilities() {
+    assert_eq!(win_probability(0), 0.5);
+    assert!((win_probability(400) - 0.75).abs() < 0.001);
+    assert!((win_probability(-400) - 0.25).abs() < 0.001);
+    assert_eq!(win_probability(WIN - 5), 1.);
+    assert_eq!(win_probability(5 - WIN), 0.);
+}
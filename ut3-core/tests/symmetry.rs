@@ -0,0 +1,84 @@
+mod common;
+
+use common::{random_game, Rng};
+use ut3_core::{
+    minisquare_of,
+    symmetry::{canonical_history, Symmetry},
+    target_of, Position, Ruleset,
+};
+
+#[test]
+fn symmetries_are_permutations_that_keep_minisquares_together() {
+    for symmetry in Symmetry::ALL {
+        let mut seen = [false; 81];
+        for coord in 0..81 {
+            let moved = symmetry.apply(coord);
+            assert!(!seen[moved]);
+            seen[moved] = true;
+            assert_eq!(symmetry.inverse().apply(moved), coord);
+            assert_eq!(
+                minisquare_of(moved),
+                symmetry.apply_to_minisquare(minisquare_of(coord))
+            );
+            assert_eq!(
+                target_of(moved),
+                symmetry.apply_to_minisquare(target_of(coord))
+            );
+        }
+    }
+    assert_eq!(Symmetry::Rotate90.apply(0), 8);
+    assert_eq!(Symmetry::FlipVertical.apply(0), 72);
+    assert_eq!(Symmetry::FlipAntiDiagonal.apply(0), 80);
+}
+
+#[test]
+fn transformed_games_are_the_same_game_replayed() {
+    let mut rng = Rng(0x5e7e_e7e5);
+    for game in 0..64 {
+        let rules = Ruleset::from_bits((game % 8) as u8).unwrap();
+        let position = random_game(&mut rng, rules, 81);
+        for symmetry in Symmetry::ALL {
+            let transformed = position.transformed(symmetry);
+            let mut replayed = Position::with_rules(rules);
+            for &coord in transformed.history() {
+                replayed.try_play(coord).unwrap();
+            }
+            assert_eq!(replayed, transformed);
+            assert_eq!(replayed.to_string(), transformed.to_string());
+            assert_eq!(replayed.zobrist(), transformed.zobrist());
+            assert_eq!(transformed.transformed(symmetry.inverse()), position);
+        }
+    }
+}
+
+#[test]
+fn all_symmetries_have_the_same_canonical_form() {
+    let mut rng = Rng(0xca70_0001);
+    for game in 0..64 {
+        let position = random_game(&mut rng, Ruleset::STANDARD, game);
+        let canonical = position.canonical();
+        for symmetry in Symmetry::ALL {
+            let transformed = position.transformed(symmetry);
+            assert_eq!(transformed.canonical(), canonical);
+            assert_eq!(transformed.canonical_zobrist(), canonical.zobrist());
+            let (_, moves) = canonical_history(transformed.history());
+            assert_eq!(moves, canonical_history(position.history()).1);
+        }
+    }
+}
+
+#[test]
+fn undo_works_after_transforming() {
+    let mut position = Position::new();
+    for coord in [40, 30, 10] {
+        position.try_play(coord).unwrap();
+    }
+    position.undo();
+    let mut transformed = position.transformed(Symmetry::Rotate90);
+    assert_eq!(
+        transformed.redo().map(|effects| effects.coord),
+        Some(Symmetry::Rotate90.apply(10))
+    );
+    while transformed.undo().is_some() {}
+    assert_eq!(transformed, Position::new());
+}
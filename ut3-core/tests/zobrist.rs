@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+
+use ut3_core::{BitPosition, Position};
+
+fn visit(bits: &mut BitPosition, depth: u32, seen: &mut HashMap<u64, BitPosition>) -> usize {
+    let mut transpositions = 0;
+    if let Some(other) = seen.insert(bits.zobrist(), *bits) {
+        assert_eq!(other, *bits, "hash collision");
+        transpositions += 1;
+    }
+    if depth > 0 {
+        for &coord in bits.legal_moves().iter() {
+            let unmake = bits.make(coord as usize);
+            transpositions += visit(bits, depth - 1, seen);
+            bits.unmake(coord as usize, unmake);
+        }
+    }
+    transpositions
+}
+
+#[test]
+fn transpositions_hash_the_same() {
+    let mut seen = HashMap::new();
+    let transpositions = visit(&mut BitPosition::new(), 4, &mut seen);
+    assert!(transpositions > 0);
+}
+
+#[test]
+fn positions_are_equal_regardless_of_move_order() {
+    // both orders leave nought having sent cross back to the top-left minisquare
+    let mut a = Position::new();
+    let mut b = Position::new();
+    for coord in [1, 3, 2, 6] {
+        a.try_play(coord).unwrap();
+    }
+    for coord in [2, 6, 1, 3] {
+        b.try_play(coord).unwrap();
+    }
+    assert_ne!(a.history(), b.history());
+    assert_eq!(a, b);
+    assert_eq!(a.zobrist(), b.zobrist());
+}